@@ -0,0 +1,181 @@
+#ifndef LLM_JSON_H
+#define LLM_JSON_H
+
+/* Generated with cbindgen, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of a call, mirroring [`JsonRepairError`]
+ */
+typedef enum LlmJsonStatus {
+  LLM_JSON_STATUS_OK = 0,
+  LLM_JSON_STATUS_UNREPAIRABLE_JSON = 1,
+  LLM_JSON_STATUS_IO_ERROR = 2,
+  LLM_JSON_STATUS_SERDE_ERROR = 3,
+  LLM_JSON_STATUS_UTF8_ERROR = 4,
+  /**
+   * A required pointer was null
+   */
+  LLM_JSON_STATUS_INVALID_ARGUMENT = 5,
+  /**
+   * The library panicked, which is a bug
+   */
+  LLM_JSON_STATUS_PANIC = 6,
+  /**
+   * A number was written in a notation the options reject
+   */
+  LLM_JSON_STATUS_INVALID_NUMBER = 7,
+  /**
+   * The input holds several top-level values and the options allow one
+   */
+  LLM_JSON_STATUS_MULTIPLE_VALUES = 8,
+  /**
+   * The model elided content with a placeholder the options reject
+   */
+  LLM_JSON_STATUS_ELIDED_CONTENT = 9,
+} LlmJsonStatus;
+
+/**
+ * How a notation of numbers is repaired, see [`NumberRepair`]
+ */
+typedef enum LlmJsonNumberRepair {
+  LLM_JSON_NUMBER_REPAIR_NUMBER,
+  LLM_JSON_NUMBER_REPAIR_STRING,
+  LLM_JSON_NUMBER_REPAIR_ERROR,
+} LlmJsonNumberRepair;
+
+/**
+ * What to do with several top-level values, see [`RootValues`]
+ */
+typedef enum LlmJsonRootValues {
+  LLM_JSON_ROOT_VALUES_FIRST,
+  LLM_JSON_ROOT_VALUES_LAST,
+  LLM_JSON_ROOT_VALUES_WRAP,
+  LLM_JSON_ROOT_VALUES_ERROR,
+} LlmJsonRootValues;
+
+/**
+ * What to do with placeholders for elided content, see [`Placeholders`]
+ */
+typedef enum LlmJsonPlaceholders {
+  LLM_JSON_PLACEHOLDERS_KEEP,
+  LLM_JSON_PLACEHOLDERS_DROP,
+  LLM_JSON_PLACEHOLDERS_NULL,
+  LLM_JSON_PLACEHOLDERS_ERROR,
+} LlmJsonPlaceholders;
+
+/**
+ * How each notation of numbers is repaired, see [`NumberOptions`]
+ *
+ * Each field is one of [`LlmJsonNumberRepair`].
+ */
+typedef struct LlmJsonNumberOptions {
+  int leading_zeros;
+  int plus_sign;
+  int bare_decimal_point;
+  int thousands_separators;
+  int decimal_comma;
+  int percent;
+  int currency;
+  int magnitude_suffix;
+  int unit;
+} LlmJsonNumberOptions;
+
+/**
+ * Options for a call, see [`RepairOptions`]
+ */
+typedef struct LlmJsonOptions {
+  bool skip_json_loads;
+  bool ensure_ascii;
+  bool stream_stable;
+  bool skip_reasoning;
+  /**
+   * Tag names of reasoning sections as NUL-terminated strings, null for the defaults
+   */
+  const char *const *reasoning_tags;
+  size_t reasoning_tags_len;
+  struct LlmJsonNumberOptions numbers;
+  /**
+   * One of [`LlmJsonRootValues`]
+   */
+  int root_values;
+  bool index_maps;
+  /**
+   * One of [`LlmJsonPlaceholders`]
+   */
+  int placeholders;
+} LlmJsonOptions;
+
+/**
+ * Error details filled in by failing calls
+ */
+typedef struct LlmJsonError {
+  enum LlmJsonStatus code;
+  /**
+   * Human-readable description, null on success
+   */
+  char *message;
+} LlmJsonError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The default options
+ */
+struct LlmJsonOptions llm_json_default_options(void);
+
+/**
+ * Repair the `len` bytes of UTF-8 at `input`
+ *
+ * On success `*out` receives the repaired JSON as a NUL-terminated string. On failure
+ * `*out` is set to null and `err`, if not null, receives the error. `options` may be
+ * null for the defaults.
+ *
+ * # Safety
+ *
+ * `input` must point to `len` readable bytes, `out` must be a valid pointer, and
+ * `options` and `err` must be null or valid pointers.
+ */
+enum LlmJsonStatus llm_json_repair(const char *input,
+                                   size_t len,
+                                   const struct LlmJsonOptions *options,
+                                   char **out,
+                                   struct LlmJsonError *err);
+
+/**
+ * Repair the `len` bytes of UTF-8 at `input` and validate the result
+ *
+ * Works like [`llm_json_repair`], except that the output is always validated, as with
+ * `loads`, even when `skip_json_loads` is set.
+ *
+ * # Safety
+ *
+ * Same as [`llm_json_repair`].
+ */
+enum LlmJsonStatus llm_json_loads(const char *input,
+                                  size_t len,
+                                  const struct LlmJsonOptions *options,
+                                  char **out,
+                                  struct LlmJsonError *err);
+
+/**
+ * Release a string returned by this library; null is ignored
+ *
+ * # Safety
+ *
+ * `string` must be null or a string returned by this library that was not freed yet.
+ */
+void llm_json_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LLM_JSON_H */
@@ -0,0 +1,215 @@
+//! Async entry points, enabled with the `async` feature
+//!
+//! Repairing is CPU-bound and fast, so these functions only take care of collecting the
+//! input without blocking the runtime; the repair itself runs inline.
+
+use crate::{JsonRepairError, RepairOptions, loads};
+use futures_core::Stream;
+use serde_json::Value;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Repair and parse JSON from an async reader
+///
+/// # Arguments
+///
+/// * `reader` - An async reader containing JSON data
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The parsed JSON value
+/// * `Err(JsonRepairError)` - If the reader cannot be read or JSON cannot be repaired
+pub async fn load_async<R: AsyncRead + Unpin>(
+    mut reader: R,
+    options: &RepairOptions,
+) -> Result<Value, JsonRepairError> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content).await?;
+    loads(&content, options)
+}
+
+/// Repair and parse JSON from a stream of byte chunks, such as an HTTP body
+///
+/// # Arguments
+///
+/// * `stream` - A stream of byte chunks containing JSON data
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(Value)` - The parsed JSON value once the stream is exhausted
+/// * `Err(JsonRepairError)` - If the chunks are not valid UTF-8 or JSON cannot be repaired
+pub async fn load_stream<S, B>(stream: S, options: &RepairOptions) -> Result<Value, JsonRepairError>
+where
+    S: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    let mut stream = repair_stream(stream, options);
+    std::future::poll_fn(|cx| stream.poll_collect(cx)).await;
+    loads(std::str::from_utf8(&stream.buffer)?, options)
+}
+
+/// Wrap a stream of byte chunks into a stream of progressively repaired snapshots
+///
+/// Every chunk that changes the repaired value yields the repair of everything received
+/// so far, so the last item is the final value. Each snapshot repairs the whole buffer
+/// again; prefer [`load_stream`] when only the final value is needed.
+///
+/// # Examples
+///
+/// ```rust
+/// use futures::{StreamExt, executor::block_on, stream};
+/// use llm_json::{RepairOptions, repair_stream};
+///
+/// let chunks = stream::iter(vec![&b"{\"items\": [1, "[..], &b"2, 3]}"[..]]);
+/// let snapshots: Vec<_> = block_on(repair_stream(chunks, &RepairOptions::default()).collect());
+///
+/// assert_eq!(snapshots[0].as_ref().unwrap()["items"], serde_json::json!([1]));
+/// assert_eq!(snapshots[1].as_ref().unwrap()["items"], serde_json::json!([1, 2, 3]));
+/// ```
+pub fn repair_stream<S>(stream: S, options: &RepairOptions) -> RepairStream<S> {
+    RepairStream {
+        stream,
+        buffer: Vec::new(),
+        options: options.clone(),
+        last: None,
+    }
+}
+
+/// Stream of repaired snapshots, created by [`repair_stream`]
+#[derive(Debug)]
+pub struct RepairStream<S> {
+    stream: S,
+    buffer: Vec<u8>,
+    options: RepairOptions,
+    /// The last snapshot yielded, not repeated when a chunk leaves it unchanged
+    last: Option<Value>,
+}
+
+impl<S> RepairStream<S> {
+    /// The longest valid UTF-8 prefix of the buffer, leaving room for a split character
+    fn decoded(&self) -> Result<&str, JsonRepairError> {
+        match std::str::from_utf8(&self.buffer) {
+            Ok(text) => Ok(text),
+            Err(e) if e.error_len().is_none() => {
+                // The last character is incomplete, the next chunk carries the rest
+                Ok(std::str::from_utf8(&self.buffer[..e.valid_up_to()])?)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<S, B> RepairStream<S>
+where
+    S: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    /// Buffer every remaining chunk without producing snapshots
+    fn poll_collect(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        while let Some(chunk) = ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+            self.buffer.extend_from_slice(chunk.as_ref());
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<S, B> Stream for RepairStream<S>
+where
+    S: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    type Item = Result<Value, JsonRepairError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(chunk) = ready!(Pin::new(&mut this.stream).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            if chunk.as_ref().is_empty() {
+                continue;
+            }
+            this.buffer.extend_from_slice(chunk.as_ref());
+
+            let text = match this.decoded() {
+                Ok(text) => text,
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            };
+            // Nothing to repair yet, wait for more input
+            if text.trim().is_empty() {
+                continue;
+            }
+            let snapshot = match loads(text, &this.options) {
+                Ok(snapshot) => snapshot,
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            };
+            if this.last.as_ref() == Some(&snapshot) {
+                continue;
+            }
+            this.last = Some(snapshot.clone());
+            return Poll::Ready(Some(Ok(snapshot)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{StreamExt, stream};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_load_async() {
+        let reader: &[u8] = br#"{name: 'John', age: 30,"#;
+        let value = load_async(reader, &RepairOptions::default()).await.unwrap();
+        assert_eq!(value, json!({"name": "John", "age": 30}));
+    }
+
+    #[tokio::test]
+    async fn test_repair_stream_snapshots() {
+        // Neither the empty chunk nor the comma changes the snapshot
+        let chunks = stream::iter(vec!["{\"a\": \"x", "y\", \"b\": [1", "", ",", " 2]"]);
+        let snapshots: Vec<Value> = repair_stream(chunks, &RepairOptions::default())
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(
+            snapshots,
+            vec![
+                json!({"a": "x"}),
+                json!({"a": "xy", "b": [1]}),
+                json!({"a": "xy", "b": [1, 2]}),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_stream_split_utf8() {
+        let bytes = "[\"caffè\"]".as_bytes();
+        let split = bytes.len() - 3;
+        let chunks = stream::iter(vec![bytes[..split].to_vec(), bytes[split..].to_vec()]);
+
+        let options = RepairOptions {
+            ensure_ascii: false,
+            ..Default::default()
+        };
+        let value = load_stream(chunks, &options).await.unwrap();
+        assert_eq!(value, json!(["caffè"]));
+
+        // Snapshots never fail on a character split across chunks
+        let chunks = stream::iter(vec![bytes[..split].to_vec(), bytes[split..].to_vec()]);
+        let snapshots: Vec<_> = repair_stream(chunks, &options).collect().await;
+        assert!(snapshots.iter().all(Result::is_ok));
+    }
+
+    #[tokio::test]
+    async fn test_load_stream_invalid_utf8() {
+        let chunks = stream::iter(vec![vec![b'[', 0xff, b']']]);
+        let result = load_stream(chunks, &RepairOptions::default()).await;
+        assert!(matches!(result, Err(JsonRepairError::Utf8Error(_))));
+    }
+}
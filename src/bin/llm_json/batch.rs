@@ -0,0 +1,311 @@
+//! Repairing many files at once
+
+use crate::convert::Format;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use llm_json::{RepairKind, RepairOptions, repair_bytes, repair_json_minimal};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of repair kinds listed in the summary
+const TOP_REPAIR_KINDS: usize = 10;
+
+/// Where and how repaired files are written
+pub struct BatchConfig {
+    pub output_dir: Option<PathBuf>,
+    pub inline: bool,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub jobs: Option<usize>,
+    pub indent: usize,
+    pub format: Format,
+    pub options: RepairOptions,
+}
+
+/// Whether a path argument should be handled in batch mode
+pub fn is_batch_path(path: &str) -> bool {
+    Path::new(path).is_dir() || (!Path::new(path).exists() && is_glob(path))
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '[', '{'])
+}
+
+/// A file to repair and its path relative to the directory or glob it was found in
+pub struct Job {
+    pub source: PathBuf,
+    relative: PathBuf,
+}
+
+enum Outcome {
+    Valid,
+    Repaired(Vec<RepairKind>),
+    Failed(String),
+}
+
+/// Repair every file found in `paths` and print a summary
+///
+/// Fails once all files are processed if any of them could not be repaired.
+pub fn run(paths: &[String], config: &BatchConfig) -> Result<(), Box<dyn Error>> {
+    if config.output_dir.is_none() && !config.inline {
+        return Err("Batch mode needs --output-dir or --inline".into());
+    }
+
+    let jobs = collect_jobs(paths, config)?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.jobs.unwrap_or(0))
+        .build()?;
+    let outcomes: Vec<Outcome> = pool.install(|| {
+        jobs.par_iter()
+            .map(|job| process(job, config).unwrap_or_else(|e| Outcome::Failed(e.to_string())))
+            .collect()
+    });
+
+    let mut valid = 0;
+    let mut repaired = 0;
+    let mut failed = 0;
+    let mut kinds: HashMap<RepairKind, usize> = HashMap::new();
+    for (job, outcome) in jobs.iter().zip(&outcomes) {
+        match outcome {
+            Outcome::Valid => valid += 1,
+            Outcome::Repaired(repairs) => {
+                repaired += 1;
+                for kind in repairs {
+                    *kinds.entry(*kind).or_default() += 1;
+                }
+            }
+            Outcome::Failed(e) => {
+                failed += 1;
+                eprintln!("{}: {}", job.source.display(), e);
+            }
+        }
+    }
+
+    println!("{:<10}{:>8}", "Files", jobs.len());
+    println!("{:<10}{:>8}", "Valid", valid);
+    println!("{:<10}{:>8}", "Repaired", repaired);
+    println!("{:<10}{:>8}", "Failed", failed);
+    if !kinds.is_empty() {
+        let mut kinds: Vec<_> = kinds.into_iter().collect();
+        kinds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        println!();
+        println!("Most common repairs");
+        for (kind, count) in kinds.into_iter().take(TOP_REPAIR_KINDS) {
+            println!("  {:<32}{:>8}", kind.to_string(), count);
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} files failed", failed, jobs.len()).into());
+    }
+    Ok(())
+}
+
+/// Expand files, directories and globs into the list of files to repair
+pub fn collect_jobs(paths: &[String], config: &BatchConfig) -> Result<Vec<Job>, Box<dyn Error>> {
+    let include = glob_set(&config.include)?;
+    let exclude = glob_set(&config.exclude)?;
+    let wanted = |relative: &Path| {
+        (config.include.is_empty() || include.is_match(relative)) && !exclude.is_match(relative)
+    };
+
+    let mut jobs = Vec::new();
+    for path in paths {
+        let root = Path::new(path);
+        if root.is_file() {
+            // Files named explicitly are always repaired
+            jobs.push(Job {
+                source: root.to_path_buf(),
+                relative: root.file_name().map(PathBuf::from).unwrap_or_default(),
+            });
+        } else if root.is_dir() {
+            for source in walk(root)? {
+                let relative = source.strip_prefix(root)?.to_path_buf();
+                if wanted(&relative) {
+                    jobs.push(Job { source, relative });
+                }
+            }
+        } else if is_glob(path) {
+            let base = glob_base(root);
+            let walk_root = if base.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                &base
+            };
+            let pattern = GlobBuilder::new(path)
+                .literal_separator(true)
+                .build()?
+                .compile_matcher();
+            for found in walk(walk_root)? {
+                let relative = found.strip_prefix(walk_root)?.to_path_buf();
+                let source = base.join(&relative);
+                if pattern.is_match(&source) && wanted(&relative) {
+                    jobs.push(Job { source, relative });
+                }
+            }
+        } else {
+            return Err(format!("No such file or directory: '{}'", path).into());
+        }
+    }
+
+    let mut sources = HashSet::new();
+    jobs.retain(|job| sources.insert(job.source.clone()));
+    if let Some(output_dir) = &config.output_dir {
+        let mut targets = HashMap::new();
+        for job in &jobs {
+            if let Some(other) = targets.insert(&job.relative, &job.source) {
+                return Err(format!(
+                    "Both '{}' and '{}' would be written to '{}'",
+                    other.display(),
+                    job.source.display(),
+                    output_dir.join(&job.relative).display()
+                )
+                .into());
+            }
+        }
+    }
+    Ok(jobs)
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
+
+/// The directory a glob starts matching in: its leading components without wildcards
+fn glob_base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+/// Every file below `dir`, in a stable order
+///
+/// Symbolic links to directories are not followed, so a link cycle cannot loop forever.
+fn walk(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else if !path.is_dir() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn process(job: &Job, config: &BatchConfig) -> Result<Outcome, Box<dyn Error>> {
+    let bytes = fs::read(&job.source)?;
+    let repaired = repair_bytes(&bytes, &config.options)?;
+    let is_valid = repaired.repairs.is_empty() && repaired.decisions.is_empty();
+    let pretty = config.format.render(&repaired.json, config.indent)?;
+
+    if let Some(output_dir) = &config.output_dir {
+        let mut target = output_dir.join(&job.relative);
+        if config.format != Format::Json {
+            target.set_extension(config.format.extension());
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, &pretty)?;
+    } else if !is_valid {
+        // Valid files are left untouched in place, and UTF-8 files keep their formatting
+        match std::str::from_utf8(&bytes) {
+            Ok(text) if repaired.decisions.is_empty() => {
+                fs::write(&job.source, repair_json_minimal(text, &config.options)?)?
+            }
+            _ => fs::write(&job.source, &pretty)?,
+        }
+    }
+
+    Ok(if is_valid {
+        Outcome::Valid
+    } else {
+        Outcome::Repaired(repaired.repairs.iter().map(|repair| repair.kind).collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(include: &[&str], exclude: &[&str]) -> BatchConfig {
+        BatchConfig {
+            output_dir: Some(PathBuf::from("out")),
+            inline: false,
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            jobs: None,
+            indent: 2,
+            format: Format::Json,
+            options: RepairOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_collect_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        for file in ["one.json", "a/two.json", "a/b/three.json", "a/notes.txt"] {
+            fs::write(root.join(file), "{}").unwrap();
+        }
+        let relative =
+            |jobs: Vec<Job>| -> Vec<PathBuf> { jobs.into_iter().map(|job| job.relative).collect() };
+
+        let dir_arg = root.to_string_lossy().to_string();
+        let jobs = collect_jobs(
+            std::slice::from_ref(&dir_arg),
+            &config(&["*.json"], &["a/b/*"]),
+        )
+        .unwrap();
+        assert_eq!(
+            relative(jobs),
+            vec![PathBuf::from("a/two.json"), PathBuf::from("one.json")]
+        );
+
+        // A single `*` does not cross directories, `**` does
+        let glob = format!("{}/*/*.json", dir_arg);
+        let jobs = collect_jobs(&[glob], &config(&[], &[])).unwrap();
+        assert_eq!(relative(jobs), vec![PathBuf::from("a/two.json")]);
+        let glob = format!("{}/**/*.json", dir_arg);
+        assert_eq!(collect_jobs(&[glob], &config(&[], &[])).unwrap().len(), 3);
+
+        // Files that would land on the same output path are rejected
+        fs::write(root.join("a/b/one.json"), "{}").unwrap();
+        let one = root.join("one.json").to_string_lossy().to_string();
+        let nested = root.join("a/b").to_string_lossy().to_string();
+        assert!(collect_jobs(&[one.clone(), dir_arg], &config(&[], &[])).is_ok());
+        assert!(collect_jobs(&[one, nested], &config(&[], &[])).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("a/one.json"), "{}").unwrap();
+        std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("a/one.json"), root.join("link.json")).unwrap();
+
+        // Links to files are kept, links to directories are not followed
+        assert_eq!(
+            walk(root).unwrap(),
+            vec![root.join("a/one.json"), root.join("link.json")]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Validating input without writing anything
+
+use crate::batch::{self, BatchConfig};
+use llm_json::{RepairOptions, repair_bytes};
+use rayon::prelude::*;
+use std::fs;
+use std::io::{self, Read};
+
+/// The input is valid JSON
+pub const VALID: i32 = 0;
+/// The input can be repaired
+pub const REPAIRABLE: i32 = 1;
+/// The input cannot be repaired, or cannot be read
+pub const UNREPAIRABLE: i32 = 2;
+
+/// Check every input and return the worst exit code
+///
+/// The first problem of each input that is not valid is printed on stderr, as
+/// `name:line:column: problem`.
+pub fn run(paths: &[String], config: &BatchConfig) -> i32 {
+    if paths.is_empty() {
+        let mut bytes = Vec::new();
+        return match io::stdin().read_to_end(&mut bytes) {
+            Ok(_) => report("<stdin>", check(&bytes, &config.options)),
+            Err(e) => report("<stdin>", (UNREPAIRABLE, Some(Problem::new(e)))),
+        };
+    }
+
+    let jobs = match batch::collect_jobs(paths, config) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            eprintln!("{}", e);
+            return UNREPAIRABLE;
+        }
+    };
+    let results: Vec<_> = jobs
+        .par_iter()
+        .map(|job| match fs::read(&job.source) {
+            Ok(bytes) => check(&bytes, &config.options),
+            Err(e) => (UNREPAIRABLE, Some(Problem::new(e))),
+        })
+        .collect();
+    jobs.iter()
+        .zip(results)
+        .map(|(job, result)| report(&job.source.display().to_string(), result))
+        .max()
+        .unwrap_or(VALID)
+}
+
+/// The first problem found in an input
+struct Problem {
+    /// Line and column, when the problem is at a place in the text
+    location: Option<(usize, usize)>,
+    message: String,
+}
+
+impl Problem {
+    fn new(message: impl ToString) -> Self {
+        Self {
+            location: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn report(name: &str, (code, problem): (i32, Option<Problem>)) -> i32 {
+    match problem {
+        Some(Problem {
+            location: Some((line, column)),
+            message,
+        }) => eprintln!("{}:{}:{}: {}", name, line, column, message),
+        Some(Problem {
+            location: None,
+            message,
+        }) => eprintln!("{}: {}", name, message),
+        None => {}
+    }
+    code
+}
+
+/// Exit code for `bytes` and its first problem
+fn check(bytes: &[u8], options: &RepairOptions) -> (i32, Option<Problem>) {
+    let repaired = match repair_bytes(bytes, options) {
+        Ok(repaired) => repaired,
+        Err(e) => return (UNREPAIRABLE, Some(Problem::new(e))),
+    };
+    if let Some(decision) = repaired.decisions.first() {
+        return (REPAIRABLE, Some(Problem::new(decision)));
+    }
+    // Without decoding decisions the input is the UTF-8 text that was repaired
+    let text = String::from_utf8_lossy(bytes);
+    match repaired.repairs.first() {
+        Some(repair) => (
+            REPAIRABLE,
+            Some(Problem {
+                location: Some(repair.line_col(&text)),
+                message: repair.kind.to_string(),
+            }),
+        ),
+        None => (VALID, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let options = RepairOptions::default();
+        assert!(matches!(check(b"{\"a\": [1, 2]}", &options), (VALID, None)));
+
+        let (code, problem) = check(b"{\n  \"a\": [1, 2,]\n}", &options);
+        assert_eq!(code, REPAIRABLE);
+        let problem = problem.unwrap();
+        assert_eq!(problem.location, Some((2, 13)));
+        assert_eq!(problem.message, "removed extra comma");
+
+        let (code, problem) = check(b"[\"caf\xE9\"]", &options);
+        assert_eq!(code, REPAIRABLE);
+        assert_eq!(problem.unwrap().location, None);
+
+        let options = RepairOptions {
+            root_values: llm_json::RootValues::Error,
+            ..RepairOptions::default()
+        };
+        let (code, problem) = check(b"{}{}", &options);
+        assert_eq!(code, UNREPAIRABLE);
+        assert!(problem.unwrap().location.is_none());
+    }
+}
@@ -0,0 +1,194 @@
+//! Writing the repaired value in other formats
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::error::Error;
+
+/// Output format for the repaired value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+    MessagePack,
+    Cbor,
+}
+
+impl Format {
+    /// Names accepted by `--to`
+    pub const NAMES: [&'static str; 6] = ["json", "yaml", "toml", "csv", "msgpack", "cbor"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Format::Json),
+            "yaml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            "csv" => Some(Format::Csv),
+            "msgpack" => Some(Format::MessagePack),
+            "cbor" => Some(Format::Cbor),
+            _ => None,
+        }
+    }
+
+    /// File extension for files written in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Csv => "csv",
+            Format::MessagePack => "msgpack",
+            Format::Cbor => "cbor",
+        }
+    }
+
+    /// Whether the output is text rather than binary
+    pub fn is_text(self) -> bool {
+        !matches!(self, Format::MessagePack | Format::Cbor)
+    }
+
+    /// Serialize repaired JSON, pretty printing JSON with `indent` spaces
+    pub fn render(self, repaired: &str, indent: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(repaired)?;
+        let mut buf = Vec::new();
+        match self {
+            Format::Json if indent > 0 => {
+                let indent_template = b" ".repeat(indent);
+                let formatter =
+                    serde_json::ser::PrettyFormatter::with_indent(indent_template.as_slice());
+                let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
+                value.serialize(&mut ser)?;
+            }
+            Format::Json => serde_json::to_writer(&mut buf, &value)?,
+            Format::Yaml => serde_yaml::to_writer(&mut buf, &value)?,
+            Format::Toml if !value.is_object() => return Err("TOML needs an object".into()),
+            Format::Toml => {
+                let toml = toml::to_string_pretty(&value).map_err(|e| {
+                    let note = if has_null(&value) {
+                        " (TOML has no null)"
+                    } else {
+                        ""
+                    };
+                    format!("Cannot write TOML: {}{}", e, note)
+                })?;
+                buf = toml.into_bytes();
+            }
+            Format::Csv => write_csv(&mut buf, &value)?,
+            Format::MessagePack => rmp_serde::encode::write_named(&mut buf, &value)?,
+            Format::Cbor => ciborium::into_writer(&value, &mut buf)?,
+        }
+        Ok(buf)
+    }
+}
+
+/// Whether a null appears anywhere in `value`
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.iter().any(has_null),
+        Value::Object(members) => members.values().any(has_null),
+        _ => false,
+    }
+}
+
+/// Write an array of flat objects, or a single one, as CSV with a header row
+///
+/// Columns are the keys of all rows, in order of first appearance.
+fn write_csv(buf: &mut Vec<u8>, value: &Value) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<&Map<String, Value>> = match value {
+        Value::Object(row) => vec![row],
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.as_object().ok_or("CSV rows must be objects"))
+            .collect::<Result<_, _>>()?,
+        _ => return Err("CSV needs an array of objects".into()),
+    };
+
+    let mut columns: Vec<&str> = Vec::new();
+    for row in &rows {
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(buf);
+    writer.write_record(&columns)?;
+    for row in &rows {
+        let mut record = Vec::with_capacity(columns.len());
+        for column in &columns {
+            record.push(match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(text)) => text.clone(),
+                Some(Value::Array(_) | Value::Object(_)) => {
+                    return Err(format!("CSV fields must be flat, '{}' is nested", column).into());
+                }
+                Some(scalar) => scalar.to_string(),
+            });
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(format: Format, repaired: &str) -> Result<String, Box<dyn Error>> {
+        Ok(String::from_utf8(format.render(repaired, 2)?)?)
+    }
+
+    #[test]
+    fn test_text_formats() {
+        let repaired = r#"{"name":"John","tags":["a","b"]}"#;
+        assert_eq!(
+            render(Format::Json, repaired).unwrap(),
+            "{\n  \"name\": \"John\",\n  \"tags\": [\n    \"a\",\n    \"b\"\n  ]\n}"
+        );
+        assert_eq!(
+            render(Format::Yaml, repaired).unwrap(),
+            "name: John\ntags:\n- a\n- b\n"
+        );
+        assert_eq!(
+            render(Format::Toml, repaired).unwrap(),
+            "name = \"John\"\ntags = [\n    \"a\",\n    \"b\",\n]\n"
+        );
+        // TOML has no null and needs a table at the root
+        for nested in [r#"{"a":[1,null]}"#, r#"{"a":{"b":null}}"#] {
+            let error = render(Format::Toml, nested).unwrap_err();
+            assert!(error.to_string().ends_with("(TOML has no null)"));
+        }
+        let error = render(Format::Toml, "[1]").unwrap_err();
+        assert!(!error.to_string().contains("null"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let repaired =
+            r#"[{"age":30,"name":"John"},{"name":"Jane, Doe","active":true,"age":null}]"#;
+        assert_eq!(
+            render(Format::Csv, repaired).unwrap(),
+            "age,name,active\n30,John,\n,\"Jane, Doe\",true\n"
+        );
+        assert!(render(Format::Csv, r#"[{"a":[1]}]"#).is_err());
+        assert!(render(Format::Csv, "[1, 2]").is_err());
+    }
+
+    #[test]
+    fn test_binary_formats() {
+        let repaired = r#"{"a":[1,true,null]}"#;
+        let value: Value = serde_json::from_str(repaired).unwrap();
+
+        let bytes = Format::MessagePack.render(repaired, 2).unwrap();
+        assert_eq!(rmp_serde::from_slice::<Value>(&bytes).unwrap(), value);
+        let bytes = Format::Cbor.render(repaired, 2).unwrap();
+        assert_eq!(
+            ciborium::from_reader::<Value, _>(&bytes[..]).unwrap(),
+            value
+        );
+    }
+}
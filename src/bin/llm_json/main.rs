@@ -0,0 +1,261 @@
+mod batch;
+mod check;
+mod convert;
+mod options;
+mod rpc;
+mod serve;
+
+use batch::BatchConfig;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, Command, value_parser};
+use convert::Format;
+use llm_json::{RepairOptions, repair_json, repair_json_minimal};
+use serve::ServeConfig;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Command::new("json_repair")
+        .version("0.1.0")
+        .about("Repair and parse JSON files")
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("serve")
+                .about("Serve the repairer over HTTP")
+                .arg(
+                    Arg::new("address")
+                        .long("address")
+                        .value_name("HOST:PORT")
+                        .default_value("127.0.0.1:8080")
+                        .help("Address to listen on"),
+                )
+                .arg(
+                    Arg::new("max_body_bytes")
+                        .long("max-body-bytes")
+                        .value_name("BYTES")
+                        .value_parser(value_parser!(usize))
+                        .default_value("10485760")
+                        .help("Largest request body accepted"),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .long("threads")
+                        .value_name("N")
+                        .value_parser(value_parser!(usize))
+                        .help("Number of requests handled in parallel (Default: number of CPUs)"),
+                ),
+        )
+        .arg(
+            Arg::new("filename")
+                .help(
+                    "The JSON files, directories or globs to repair (if omitted, reads from stdin)",
+                )
+                .index(1)
+                .num_args(0..)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("inline")
+                .short('i')
+                .long("inline")
+                .help("Fix the file in place, changing only what is broken and keeping its formatting")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("TARGET")
+                .help(
+                    "If specified, the output will be written to TARGET filename instead of stdout",
+                ),
+        )
+        .arg(
+            Arg::new("output_dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write each repaired file to DIR, keeping paths relative to the directory or glob it was found in"),
+        )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Only repair files matching GLOB when walking directories and globs"),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Skip files matching GLOB when walking directories and globs"),
+        )
+        .arg(
+            Arg::new("jobs")
+                .short('j')
+                .long("jobs")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Number of files repaired in parallel (Default: number of CPUs)"),
+        )
+        .arg(
+            Arg::new("to")
+                .long("to")
+                .value_name("FORMAT")
+                .value_parser(PossibleValuesParser::new(Format::NAMES))
+                .default_value("json")
+                .help("Output format; CSV needs an array of flat objects"),
+        )
+        .arg(
+            Arg::new("rpc")
+                .long("rpc")
+                .help("Answer newline-delimited JSON-RPC requests (repair, loads, extract) on stdin until it closes")
+                .action(ArgAction::SetTrue)
+                .exclusive(true),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Only validate: exit 0 if valid, 1 if repairable, 2 if unrepairable, printing the first problem on stderr")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["inline", "output", "output_dir"]),
+        )
+        .arg(
+            Arg::new("ensure_ascii")
+                .long("ensure_ascii")
+                .help("Ensure ASCII output (escape Unicode characters)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("indent")
+                .long("indent")
+                .value_name("INDENT")
+                .help("Number of spaces for indentation (Default 2)")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::new("skip_json_loads")
+                .long("skip-validation")
+                .help("Skip JSON validation for performance")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("skip_reasoning")
+                .long("skip-reasoning")
+                .help("Ignore reasoning sections such as <think>...</think>")
+                .action(ArgAction::SetTrue),
+        )
+        .get_matches();
+
+    if matches.get_flag("rpc") {
+        return Ok(rpc::run(io::stdin().lock(), io::stdout().lock())?);
+    }
+
+    if let Some(("serve", serve_matches)) = matches.subcommand() {
+        return serve::run(ServeConfig {
+            address: serve_matches.get_one::<String>("address").unwrap().clone(),
+            max_body_bytes: *serve_matches.get_one("max_body_bytes").unwrap(),
+            threads: serve_matches
+                .get_one("threads")
+                .copied()
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+                }),
+        });
+    }
+
+    let options = RepairOptions {
+        skip_json_loads: matches.get_flag("skip_json_loads"),
+        return_objects: false,
+        ensure_ascii: matches.get_flag("ensure_ascii"),
+        stream_stable: false,
+        skip_reasoning: matches.get_flag("skip_reasoning"),
+        ..Default::default()
+    };
+
+    let indent: usize = matches
+        .get_one::<String>("indent")
+        .unwrap()
+        .parse()
+        .unwrap_or(2);
+
+    let paths: Vec<String> = matches
+        .get_many::<String>("filename")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    let config = BatchConfig {
+        output_dir: matches.get_one::<PathBuf>("output_dir").cloned(),
+        inline: matches.get_flag("inline"),
+        include: matches
+            .get_many("include")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        exclude: matches
+            .get_many("exclude")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+        jobs: matches.get_one("jobs").copied(),
+        indent,
+        format: Format::from_name(matches.get_one::<String>("to").unwrap()).unwrap(),
+        options,
+    };
+
+    if config.inline && config.format != Format::Json {
+        return Err("Cannot use --inline with --to, the file would not be JSON anymore".into());
+    }
+
+    if matches.get_flag("check") {
+        std::process::exit(check::run(&paths, &config));
+    }
+
+    if paths.len() > 1
+        || config.output_dir.is_some()
+        || paths.iter().any(|path| batch::is_batch_path(path))
+    {
+        if matches.contains_id("output") {
+            return Err("Use --output-dir instead of --output with several files".into());
+        }
+        return batch::run(&paths, &config);
+    }
+    let options = &config.options;
+
+    let input_content = if let Some(filename) = matches.get_one::<String>("filename") {
+        fs::read_to_string(filename)
+            .map_err(|e| format!("Failed to read file '{}': {}", filename, e))?
+    } else {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        buffer
+    };
+
+    if config.inline {
+        let Some(filename) = matches.get_one::<String>("filename") else {
+            return Err("Cannot use --inline without specifying a filename".into());
+        };
+        fs::write(filename, repair_json_minimal(&input_content, options)?)?;
+        println!("File '{}' repaired in place", filename);
+        return Ok(());
+    }
+
+    let repaired = repair_json(&input_content, options)?;
+    let pretty = config.format.render(&repaired, indent)?;
+
+    // Handle output
+    if let Some(output_file) = matches.get_one::<String>("output") {
+        fs::write(output_file, &pretty)?;
+        println!("Output written to '{}'", output_file);
+    } else {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&pretty)?;
+        if config.format.is_text() && !pretty.ends_with(b"\n") {
+            stdout.write_all(b"\n")?;
+        }
+    }
+
+    Ok(())
+}
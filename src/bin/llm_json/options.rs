@@ -0,0 +1,106 @@
+//! Repair options given as JSON, shared by the HTTP and RPC modes
+
+use llm_json::{NumberOptions, NumberRepair, Placeholders, RepairOptions, RootValues};
+use serde_json::{Map, Value};
+
+/// Override `options` with the ones named in `values`
+///
+/// Flags take booleans, `reasoning_tags` a list of strings, `root_values` one of
+/// `"first"`, `"last"`, `"wrap"` or `"error"`, `placeholders` one of `"keep"`, `"drop"`,
+/// `"null"` or `"error"`, and `numbers` an object mapping notations to `"number"`,
+/// `"string"` or `"error"`; unknown names are rejected so that typos do not go
+/// unnoticed.
+pub fn apply_options(
+    options: &mut RepairOptions,
+    values: &Map<String, Value>,
+) -> Result<(), String> {
+    for (name, value) in values {
+        let flag = || {
+            value
+                .as_bool()
+                .ok_or_else(|| format!("Option `{}` must be a boolean", name))
+        };
+        match name.as_str() {
+            "skip_json_loads" => options.skip_json_loads = flag()?,
+            "ensure_ascii" => options.ensure_ascii = flag()?,
+            "stream_stable" => options.stream_stable = flag()?,
+            "skip_reasoning" => options.skip_reasoning = flag()?,
+            "index_maps" => options.index_maps = flag()?,
+            "reasoning_tags" => {
+                options.reasoning_tags = value
+                    .as_array()
+                    .and_then(|tags| {
+                        tags.iter()
+                            .map(|tag| tag.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .ok_or("Option `reasoning_tags` must be a list of strings")?;
+            }
+            "root_values" => {
+                options.root_values = match value.as_str() {
+                    Some("first") => RootValues::First,
+                    Some("last") => RootValues::Last,
+                    Some("wrap") => RootValues::Wrap,
+                    Some("error") => RootValues::Error,
+                    _ => {
+                        return Err(
+                            "Option `root_values` must be \"first\", \"last\", \"wrap\" or \"error\""
+                                .to_string(),
+                        );
+                    }
+                };
+            }
+            "placeholders" => {
+                options.placeholders = match value.as_str() {
+                    Some("keep") => Placeholders::Keep,
+                    Some("drop") => Placeholders::Drop,
+                    Some("null") => Placeholders::Null,
+                    Some("error") => Placeholders::Error,
+                    _ => {
+                        return Err(
+                            "Option `placeholders` must be \"keep\", \"drop\", \"null\" or \"error\""
+                                .to_string(),
+                        );
+                    }
+                };
+            }
+            "numbers" => apply_number_options(&mut options.numbers, value)?,
+            _ => return Err(format!("Unknown option `{}`", name)),
+        }
+    }
+    Ok(())
+}
+
+/// Override the number options named in `value`
+fn apply_number_options(options: &mut NumberOptions, value: &Value) -> Result<(), String> {
+    let values = value
+        .as_object()
+        .ok_or("Option `numbers` must be an object")?;
+    for (name, value) in values {
+        let repair = match value.as_str() {
+            Some("number") => NumberRepair::Number,
+            Some("string") => NumberRepair::String,
+            Some("error") => NumberRepair::Error,
+            _ => {
+                return Err(format!(
+                    "Number option `{}` must be \"number\", \"string\" or \"error\"",
+                    name
+                ));
+            }
+        };
+        let option = match name.as_str() {
+            "leading_zeros" => &mut options.leading_zeros,
+            "plus_sign" => &mut options.plus_sign,
+            "bare_decimal_point" => &mut options.bare_decimal_point,
+            "thousands_separators" => &mut options.thousands_separators,
+            "decimal_comma" => &mut options.decimal_comma,
+            "percent" => &mut options.percent,
+            "currency" => &mut options.currency,
+            "magnitude_suffix" => &mut options.magnitude_suffix,
+            "unit" => &mut options.unit,
+            _ => return Err(format!("Unknown number option `{}`", name)),
+        };
+        *option = repair;
+    }
+    Ok(())
+}
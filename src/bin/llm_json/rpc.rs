@@ -0,0 +1,212 @@
+//! Long-running JSON-RPC 2.0 worker over stdio
+//!
+//! Each line on stdin is a request (or a batch of requests), each response is written as
+//! one line on stdout. Every method takes `input` and optional `options` parameters:
+//!
+//! - `repair` returns the repaired JSON string
+//! - `loads` returns the repaired and validated value
+//! - `extract` returns the value at the JSON Pointer given as `pointer`, or null
+
+use crate::options::apply_options;
+use llm_json::{RepairOptions, loads, repair_json};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The input could not be repaired
+const UNREPAIRABLE: i64 = -32000;
+
+/// Answer requests from `input` until it ends
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        // A line that is not UTF-8 fails on its own, the worker keeps serving
+        let response = match std::str::from_utf8(&line) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => handle_line(line),
+            Err(e) => Some(error_response(Value::Null, PARSE_ERROR, e)),
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+}
+
+/// The response line for a request line, `None` when only notifications were sent
+fn handle_line(line: &str) -> Option<Value> {
+    match serde_json::from_str::<Value>(line) {
+        Ok(Value::Array(batch)) if !batch.is_empty() => {
+            let responses: Vec<Value> = batch.iter().filter_map(handle_request).collect();
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        Ok(request) => handle_request(&request),
+        Err(e) => Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    }
+}
+
+fn handle_request(request: &Value) -> Option<Value> {
+    let id = request.get("id").cloned();
+    let method = request.get("method").and_then(Value::as_str);
+    let (Some(method), Some("2.0")) = (method, request.get("jsonrpc").and_then(Value::as_str))
+    else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "Not a JSON-RPC 2.0 request",
+        ));
+    };
+
+    let result = call(method, request.get("params").unwrap_or(&Value::Null));
+    // Notifications get no response
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: impl ToString) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message.to_string()},
+    })
+}
+
+fn call(method: &str, params: &Value) -> Result<Value, (i64, String)> {
+    if !matches!(method, "repair" | "loads" | "extract") {
+        return Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method)));
+    }
+    let invalid = |message: String| (INVALID_PARAMS, message);
+
+    let input = params
+        .get("input")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("`input` must be a string".to_string()))?;
+    let mut options = RepairOptions::default();
+    if let Some(overrides) = params.get("options") {
+        let overrides = overrides
+            .as_object()
+            .ok_or_else(|| invalid("`options` must be an object".to_string()))?;
+        apply_options(&mut options, overrides).map_err(invalid)?;
+    }
+    let unrepairable = |e: llm_json::JsonRepairError| (UNREPAIRABLE, e.to_string());
+
+    match method {
+        "repair" => Ok(Value::String(
+            repair_json(input, &options).map_err(unrepairable)?,
+        )),
+        "loads" => loads(input, &options).map_err(unrepairable),
+        _ => {
+            let pointer = params
+                .get("pointer")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("`pointer` must be a string".to_string()))?;
+            let value = loads(input, &options).map_err(unrepairable)?;
+            Ok(value.pointer(pointer).cloned().unwrap_or(Value::Null))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(requests: &str) -> Vec<Value> {
+        let mut output = Vec::new();
+        run(requests.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_methods() {
+        let requests = [
+            json!({"jsonrpc": "2.0", "id": 1, "method": "repair", "params": {"input": "{a: 1,"}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "loads", "params": {"input": "[1, 2"}}),
+            json!({"jsonrpc": "2.0", "id": "x", "method": "extract", "params": {
+                "input": "<think>{}</think>{steps: [{name: 'a'}, {name: 'b'",
+                "pointer": "/steps/1/name",
+                "options": {"skip_reasoning": true},
+            }}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "loads", "params": {
+                "input": "{price: $12.50}",
+                "options": {"numbers": {"currency": "number"}},
+            }}),
+            // Notifications are processed without a response
+            json!({"jsonrpc": "2.0", "method": "repair", "params": {"input": "["}}),
+        ];
+        let lines: Vec<String> = requests.iter().map(Value::to_string).collect();
+        assert_eq!(
+            exchange(&lines.join("\n\n")),
+            vec![
+                json!({"jsonrpc": "2.0", "id": 1, "result": "{\"a\":1}"}),
+                json!({"jsonrpc": "2.0", "id": 2, "result": [1, 2]}),
+                json!({"jsonrpc": "2.0", "id": "x", "result": "b"}),
+                json!({"jsonrpc": "2.0", "id": 3, "result": {"price": 12.5}}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let code = |response: &Value| response["error"]["code"].as_i64().unwrap();
+        let responses = exchange(concat!(
+            "{\"jsonrpc\": \"2.0\", \"id\": 1, \"method\": \"fix\", \"params\": {}}\n",
+            "{\"jsonrpc\": \"2.0\", \"id\": 2, \"method\": \"repair\", \"params\": {\"input\": 1}}\n",
+            "{\"jsonrpc\": \"2.0\", \"id\": 3, \"method\": \"repair\", \"params\": {\"input\": \"[\", \"options\": {\"typo\": true}}}\n",
+            "{\"id\": 4, \"method\": \"repair\"}\n",
+            "{not json\n",
+        ));
+        let codes: Vec<i64> = responses.iter().map(code).collect();
+        assert_eq!(
+            codes,
+            vec![
+                METHOD_NOT_FOUND,
+                INVALID_PARAMS,
+                INVALID_PARAMS,
+                INVALID_REQUEST,
+                PARSE_ERROR
+            ]
+        );
+        assert_eq!(responses[3]["id"], 4);
+
+        // Invalid UTF-8 fails only its own line
+        let mut output = Vec::new();
+        let input = b"\xff\n{\"jsonrpc\": \"2.0\", \"id\": 5, \"method\": \"repair\", \"params\": {\"input\": \"[\"}}\n";
+        run(&input[..], &mut output).unwrap();
+        let responses: Vec<Value> = output
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(code(&responses[0]), PARSE_ERROR);
+        assert_eq!(responses[1]["result"], "[]");
+    }
+
+    #[test]
+    fn test_batch() {
+        let batch = json!([
+            {"jsonrpc": "2.0", "id": 1, "method": "loads", "params": {"input": "{a: 1"}},
+            {"jsonrpc": "2.0", "id": 2, "method": "loads", "params": {"input": "[true"}},
+        ]);
+        assert_eq!(
+            exchange(&batch.to_string()),
+            vec![json!([
+                {"jsonrpc": "2.0", "id": 1, "result": {"a": 1}},
+                {"jsonrpc": "2.0", "id": 2, "result": [true]},
+            ])]
+        );
+    }
+}
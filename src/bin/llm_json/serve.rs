@@ -0,0 +1,352 @@
+//! Local HTTP repair service
+//!
+//! Every endpoint takes the broken text as the raw request body, with options as query
+//! parameters (`?ensure_ascii=false&reasoning_tags=think,reflect`). A body sent as
+//! `application/json` is an envelope instead: `{"input": "...", "options": {...}}`.
+//!
+//! - `POST /repair` answers with the repaired JSON
+//! - `POST /loads` answers with the repaired and validated value
+//! - `POST /report` answers with the repaired JSON, every fix applied and a confidence score
+//! - `GET /health` answers with the service status and its request limits
+
+use crate::options::apply_options;
+use llm_json::{RepairOptions, repair_bytes};
+use serde_json::{Map, Value, json};
+use std::error::Error;
+use std::io::Read;
+use std::sync::Arc;
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Where to listen and how much to accept
+pub struct ServeConfig {
+    pub address: String,
+    pub max_body_bytes: usize,
+    pub threads: usize,
+}
+
+/// Serve requests until the process is stopped
+pub fn run(config: ServeConfig) -> Result<(), Box<dyn Error>> {
+    let server = Server::http(&config.address).map_err(|e| e.to_string())?;
+    eprintln!("Listening on http://{}", server.server_addr());
+    serve(Arc::new(server), config);
+    Ok(())
+}
+
+/// Answer requests on `threads` workers until the server is unblocked
+fn serve(server: Arc<Server>, config: ServeConfig) {
+    let config = Arc::new(config);
+    let workers: Vec<_> = (0..config.threads.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let config = Arc::clone(&config);
+            thread::spawn(move || {
+                while let Ok(request) = server.recv() {
+                    handle(request, &config);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+/// An answer to a request: status code and JSON body
+type Reply = (u16, String);
+
+fn error(status: u16, message: impl ToString) -> Reply {
+    (status, json!({ "error": message.to_string() }).to_string())
+}
+
+fn handle(mut request: Request, config: &ServeConfig) {
+    let too_large = request
+        .body_length()
+        .is_some_and(|length| length > config.max_body_bytes);
+    let (status, body) = if too_large {
+        error(413, format!("Body exceeds {} bytes", config.max_body_bytes))
+    } else {
+        let mut body = Vec::new();
+        let limit = config.max_body_bytes as u64 + 1;
+        match request.as_reader().take(limit).read_to_end(&mut body) {
+            Ok(_) if body.len() > config.max_body_bytes => {
+                error(413, format!("Body exceeds {} bytes", config.max_body_bytes))
+            }
+            Ok(_) => {
+                let is_envelope = request.headers().iter().any(|header| {
+                    header.field.equiv("Content-Type")
+                        && header.value.as_str().starts_with("application/json")
+                });
+                respond(request.method(), request.url(), is_envelope, &body, config)
+            }
+            Err(e) => error(400, e),
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to answer request: {}", e);
+    }
+}
+
+/// Route a request and compute its answer
+fn respond(
+    method: &Method,
+    url: &str,
+    is_envelope: bool,
+    body: &[u8],
+    config: &ServeConfig,
+) -> Reply {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        (Method::Get, "/health") => (
+            200,
+            json!({
+                "status": "ok",
+                "version": env!("CARGO_PKG_VERSION"),
+                "limits": {
+                    "max_body_bytes": config.max_body_bytes,
+                    "threads": config.threads,
+                },
+            })
+            .to_string(),
+        ),
+        (Method::Post, "/repair" | "/loads" | "/report") => {
+            match repair(path, query, is_envelope, body) {
+                Ok(reply) => reply,
+                Err(e) => error(400, e),
+            }
+        }
+        (_, "/health" | "/repair" | "/loads" | "/report") => error(405, "Method not allowed"),
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Answer a repair endpoint; errors are problems with the request itself
+fn repair(path: &str, query: &str, is_envelope: bool, body: &[u8]) -> Result<Reply, String> {
+    let mut options = RepairOptions::default();
+    apply_options(&mut options, &query_options(query))?;
+
+    let envelope;
+    let input = if is_envelope {
+        envelope = serde_json::from_slice::<Value>(body).map_err(|e| e.to_string())?;
+        if let Some(overrides) = envelope.get("options") {
+            let overrides = overrides.as_object().ok_or("`options` must be an object")?;
+            apply_options(&mut options, overrides)?;
+        }
+        envelope
+            .get("input")
+            .and_then(Value::as_str)
+            .ok_or("The envelope needs an `input` string")?
+            .as_bytes()
+    } else {
+        body
+    };
+
+    if path == "/loads" {
+        options.skip_json_loads = false;
+    }
+    let repaired = match repair_bytes(input, &options) {
+        Ok(repaired) => repaired,
+        Err(e) => return Ok(error(422, e)),
+    };
+    if path != "/report" {
+        return Ok((200, repaired.json));
+    }
+
+    // Positions are only meaningful in text that did not need decoding
+    let text = repaired
+        .decisions
+        .is_empty()
+        .then(|| String::from_utf8_lossy(input));
+    let repairs: Vec<Value> = repaired
+        .repairs
+        .iter()
+        .map(|repair| {
+            let mut entry = json!({
+                "kind": format!("{:?}", repair.kind),
+                "message": repair.kind.to_string(),
+                "offset": repair.offset,
+                "path": repair.path,
+            });
+            if let Some(text) = &text {
+                let (line, column) = repair.line_col(text);
+                entry["line"] = json!(line);
+                entry["column"] = json!(column);
+            }
+            entry
+        })
+        .collect();
+    let report = json!({
+        "json": repaired.json,
+        "valid": repaired.repairs.is_empty() && repaired.decisions.is_empty(),
+        "confidence": repaired.confidence(),
+        "encoding": repaired.encoding.to_string(),
+        "decisions": repaired.decisions.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        "repairs": repairs,
+    });
+    Ok((200, report.to_string()))
+}
+
+/// Options given as query parameters, in the shape of an envelope's options
+fn query_options(query: &str) -> Map<String, Value> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, "true"));
+            let value = percent_decode(value);
+            let value = match value.as_str() {
+                "true" | "1" => Value::Bool(true),
+                "false" | "0" => Value::Bool(false),
+                _ if name == "reasoning_tags" => value.split(',').map(|tag| json!(tag)).collect(),
+                _ => Value::String(value),
+            };
+            (percent_decode(name), value)
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, hex) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            (b'+', _) => {
+                bytes.push(b' ');
+                rest = tail;
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    fn config() -> ServeConfig {
+        ServeConfig {
+            address: "127.0.0.1:0".to_string(),
+            max_body_bytes: 64,
+            threads: 2,
+        }
+    }
+
+    fn post(url: &str, body: &str) -> Reply {
+        respond(&Method::Post, url, false, body.as_bytes(), &config())
+    }
+
+    #[test]
+    fn test_endpoints() {
+        assert_eq!(
+            post("/repair", "{b: 1, a: 'x',"),
+            (200, "{\"a\":\"x\",\"b\":1}".to_string())
+        );
+        // Without validation keys keep their order
+        assert_eq!(
+            post("/repair?skip_json_loads=true", "{b: 1, a: 'x',"),
+            (200, "{\"b\":1,\"a\":\"x\"}".to_string())
+        );
+        assert_eq!(
+            post(
+                "/loads?skip_reasoning=1&reasoning_tags=plan%2Cthink",
+                "<plan>[0]</plan>[1"
+            ),
+            (200, "[1]".to_string())
+        );
+
+        let (status, body) = post("/report", "[1, 2,\n 3");
+        assert_eq!(status, 200);
+        let report: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["json"], "[1,2,3]");
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["confidence"], 0.98);
+        assert_eq!(
+            report["repairs"][0],
+            json!({"kind": "ClosedArray", "message": "closed unterminated array", "offset": 9, "path": "", "line": 2, "column": 3})
+        );
+
+        assert_eq!(post("/repair?bogus=1", "[").0, 400);
+        assert_eq!(post("/missing", "[").0, 404);
+        assert_eq!(
+            respond(&Method::Get, "/repair", false, b"", &config()).0,
+            405
+        );
+    }
+
+    #[test]
+    fn test_envelope() {
+        let envelope = json!({"input": "{a: 'é'", "options": {"ensure_ascii": false}});
+        let reply = respond(
+            &Method::Post,
+            "/repair?ensure_ascii=true",
+            true,
+            envelope.to_string().as_bytes(),
+            &config(),
+        );
+        assert_eq!(reply, (200, "{\"a\":\"é\"}".to_string()));
+
+        let reply = respond(&Method::Post, "/repair", true, b"{\"text\": 1}", &config());
+        assert_eq!(reply.0, 400);
+    }
+
+    #[test]
+    fn test_localhost() {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let address = server.server_addr().to_ip().unwrap();
+        let worker = {
+            let server = Arc::clone(&server);
+            thread::spawn(move || serve(server, config()))
+        };
+
+        let request = |raw: String| -> String {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(raw.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let post = |body: &str| {
+            request(format!(
+                "POST /repair HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ))
+        };
+
+        let response = post("{a: [1, 2");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("{\"a\":[1,2]}"));
+
+        let response = post(&"[1, ".repeat(20));
+        assert!(response.starts_with("HTTP/1.1 413"));
+
+        let response = request(
+            "GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n".to_string(),
+        );
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("\"max_body_bytes\":64"));
+
+        server.unblock();
+        server.unblock();
+        worker.join().unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+//! The repair as text edits on the input
+
+use crate::parser::JsonRepairParser;
+use crate::{JsonRepairError, RepairOptions};
+use std::ops::Range;
+
+/// Replacement of a range of the input
+///
+/// An empty range inserts `new_text`, an empty `new_text` deletes the range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the input to replace
+    pub range: Range<usize>,
+    /// The same range in UTF-16 code units, as most editors count positions
+    pub utf16_range: Range<usize>,
+    /// Text to put in place of the range
+    pub new_text: String,
+}
+
+/// Repair a broken JSON string into the edits that fix it
+///
+/// The edits are the ones [`repair_json_minimal`](crate::repair_json_minimal) applies:
+/// they are sorted, do not overlap, and leave everything that is not broken as it is,
+/// so editors can offer them as quick fixes.
+///
+/// # Arguments
+///
+/// * `json_str` - The broken JSON string to repair
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(Vec<TextEdit>)` - The edits, empty when the input is valid
+/// * `Err(JsonRepairError)` - If the JSON is too broken to repair
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{apply_edits, repair_json_edits, RepairOptions};
+///
+/// let broken = "{\"emoji\": \"🎉\", n: 1,}";
+/// let edits = repair_json_edits(broken, &RepairOptions::default()).unwrap();
+/// assert_eq!(edits[0].new_text, "\"n\"");
+/// // The emoji is 4 bytes but 2 UTF-16 code units
+/// assert_eq!(edits[0].range, 18..19);
+/// assert_eq!(edits[0].utf16_range, 16..17);
+/// assert_eq!(apply_edits(broken, &edits), "{\"emoji\": \"🎉\", \"n\": 1}");
+/// ```
+pub fn repair_json_edits(
+    json_str: &str,
+    options: &RepairOptions,
+) -> Result<Vec<TextEdit>, JsonRepairError> {
+    let edits = minimal_edits(json_str, options)?;
+    if !options.skip_json_loads {
+        serde_json::from_str::<::serde::de::IgnoredAny>(&apply_edits(json_str, &edits))?;
+    }
+    Ok(edits)
+}
+
+/// Apply sorted, non-overlapping `edits` to `input`
+pub fn apply_edits(input: &str, edits: &[TextEdit]) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut copied = 0;
+    for edit in edits {
+        output.push_str(&input[copied..edit.range.start]);
+        output.push_str(&edit.new_text);
+        copied = edit.range.end;
+    }
+    output.push_str(&input[copied..]);
+    output
+}
+
+/// The fewest replacements of input byte ranges that repair `json_str`, in order
+fn minimal_edits(
+    json_str: &str,
+    options: &RepairOptions,
+) -> Result<Vec<TextEdit>, JsonRepairError> {
+    let mut replacements = Vec::new();
+    if json_str.trim().is_empty() {
+        replacements.push((json_str.len()..json_str.len(), "{}".to_string()));
+        return Ok(with_utf16_ranges(json_str, replacements));
+    }
+
+    let options = RepairOptions {
+        ensure_ascii: false,
+        ..options.clone()
+    };
+    let mut parser = JsonRepairParser::aligned(json_str, options);
+    parser.parse()?;
+    let alignment = parser.take_alignment();
+    let output = parser.get_result();
+
+    let mut end = 0;
+    for (input, written) in alignment {
+        end = input.end;
+        let (original, written) = (&json_str[input.clone()], &output[written]);
+        if original == written || (written.is_empty() && original.trim().is_empty()) {
+            continue;
+        }
+        // Only the middle differs when the ends are the same
+        let prefix: usize = original
+            .chars()
+            .zip(written.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        let suffix: usize = original[prefix..]
+            .chars()
+            .rev()
+            .zip(written[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        let range = input.start + prefix..input.end - suffix;
+        let new_text = &written[prefix..written.len() - suffix];
+        let earliest = replacements.last().map_or(0, |(range, _)| range.end);
+        replacements.push(match new_text {
+            "" => (whole_line(json_str, range, earliest), String::new()),
+            _ => (range, new_text.to_string()),
+        });
+    }
+
+    // Text after the JSON goes, the newline ending the JSON stays
+    let rest = json_str[end..].trim_end();
+    if !rest.trim_start().is_empty() {
+        let earliest = replacements.last().map_or(0, |(range, _)| range.end);
+        let range = whole_line(json_str, end..end + rest.len(), earliest);
+        replacements.push((range, String::new()));
+    }
+    Ok(with_utf16_ranges(json_str, replacements))
+}
+
+/// The lines `range` covers, with their indentation and newline, when it deletes all
+/// that is on them
+///
+/// The line is only taken back to `earliest`, where the previous edit ends.
+fn whole_line(input: &str, range: Range<usize>, earliest: usize) -> Range<usize> {
+    let is_blank = |ch: char| ch == ' ' || ch == '\t';
+    let start = input[..range.start].trim_end_matches(is_blank).len();
+    let line_start = start == 0 || input[..start].ends_with('\n');
+    let rest = &input[range.end..];
+    let after = rest.len() - rest.trim_start_matches(is_blank).len();
+    let line_end = input[..range.end].ends_with('\n') || rest[after..].starts_with('\n');
+    if !line_start || !line_end || start < earliest {
+        return range;
+    }
+    let end = if input[..range.end].ends_with('\n') {
+        range.end
+    } else {
+        range.end + after + 1
+    };
+    start..end
+}
+
+/// Edits from sorted byte range replacements, counting UTF-16 offsets along the way
+fn with_utf16_ranges(input: &str, replacements: Vec<(Range<usize>, String)>) -> Vec<TextEdit> {
+    let utf16_len = |text: &str| text.chars().map(char::len_utf16).sum::<usize>();
+    let (mut byte, mut utf16) = (0, 0);
+    replacements
+        .into_iter()
+        .map(|(range, new_text)| {
+            let start = utf16 + utf16_len(&input[byte..range.start]);
+            let end = start + utf16_len(&input[range.clone()]);
+            (byte, utf16) = (range.end, end);
+            TextEdit {
+                range,
+                utf16_range: start..end,
+                new_text,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_json_edits() {
+        let options = RepairOptions::default();
+        let edit = |range: Range<usize>, utf16_range: Range<usize>, new_text: &str| TextEdit {
+            range,
+            utf16_range,
+            new_text: new_text.to_string(),
+        };
+
+        assert_eq!(
+            repair_json_edits("{\"a\": [1]}\n", &options).unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            repair_json_edits("  ", &options).unwrap(),
+            vec![edit(2..2, 2..2, "{}")]
+        );
+
+        // Insertion, deletion and replacement, after a character outside the BMP
+        let broken = "{\"𝄞\": [1 2,], k: None";
+        let edits = repair_json_edits(broken, &options).unwrap();
+        assert_eq!(
+            edits,
+            vec![
+                edit(12..12, 10..10, ","),
+                edit(13..14, 11..12, ""),
+                edit(17..18, 15..16, "\"k\""),
+                edit(20..24, 18..22, "null"),
+                edit(24..24, 22..22, "}"),
+            ]
+        );
+        assert_eq!(apply_edits(broken, &edits), "{\"𝄞\": [1 ,2], \"k\": null}");
+    }
+}
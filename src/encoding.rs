@@ -0,0 +1,256 @@
+//! Decoding of byte input whose encoding is not known in advance
+
+use std::fmt;
+
+/// Text encoding the input was decoded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Windows-1252, a superset of Latin-1 for printable characters
+    Windows1252,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "Windows-1252",
+        })
+    }
+}
+
+/// A choice made while turning bytes into text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodingDecision {
+    /// A byte order mark was removed from the start of the input
+    BomStripped(Encoding),
+    /// The input is not UTF-8 and holds no multi-byte UTF-8 character, so each byte was
+    /// read as a Windows-1252 character
+    Windows1252Fallback,
+    /// `len` bytes at `offset` did not form a character and became U+FFFD
+    Replaced { offset: usize, len: usize },
+}
+
+impl fmt::Display for DecodingDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodingDecision::BomStripped(encoding) => write!(f, "stripped {} BOM", encoding),
+            DecodingDecision::Windows1252Fallback => {
+                f.write_str("input is not UTF-8, decoded as Windows-1252")
+            }
+            DecodingDecision::Replaced { offset, len } => write!(
+                f,
+                "replaced {} invalid byte(s) at offset {} with U+FFFD",
+                len, offset
+            ),
+        }
+    }
+}
+
+/// Text decoded from bytes, with how it was decoded
+pub(crate) struct Decoded {
+    pub(crate) text: String,
+    pub(crate) encoding: Encoding,
+    pub(crate) decisions: Vec<DecodingDecision>,
+}
+
+/// Characters for bytes 0x80-0x9F, the range where Windows-1252 differs from Latin-1
+///
+/// The five bytes Windows-1252 leaves undefined map to the Latin-1 control characters.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Decode bytes of unknown encoding
+///
+/// A byte order mark decides between UTF-8 and UTF-16. Without one the input is read as
+/// UTF-8, unless it is invalid and holds no multi-byte UTF-8 character at all, which is
+/// how single-byte Windows-1252 text looks. Anything left that does not decode becomes
+/// U+FFFD.
+pub(crate) fn decode(bytes: &[u8]) -> Decoded {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        let mut decoded = decode_utf8(rest, 3);
+        decoded
+            .decisions
+            .insert(0, DecodingDecision::BomStripped(Encoding::Utf8));
+        return decoded;
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, Encoding::Utf16Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, Encoding::Utf16Be);
+    }
+
+    let decoded = decode_utf8(bytes, 0);
+    if decoded.decisions.is_empty()
+        || decoded
+            .text
+            .chars()
+            .any(|c| c > '\u{7F}' && c != '\u{FFFD}')
+    {
+        return decoded;
+    }
+    Decoded {
+        text: bytes.iter().map(|&byte| windows_1252_char(byte)).collect(),
+        encoding: Encoding::Windows1252,
+        decisions: vec![DecodingDecision::Windows1252Fallback],
+    }
+}
+
+fn windows_1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+/// Decode UTF-8, replacing invalid sequences; `base` is the offset of `bytes` in the input
+fn decode_utf8(bytes: &[u8], base: usize) -> Decoded {
+    let mut text = String::with_capacity(bytes.len());
+    let mut decisions = Vec::new();
+    let mut rest = bytes;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                text.push_str(valid);
+                break;
+            }
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                text.push_str(std::str::from_utf8(valid).expect("prefix was validated"));
+                text.push(char::REPLACEMENT_CHARACTER);
+                // A sequence cut short by the end of the input has no error length
+                let len = e.error_len().unwrap_or(invalid.len());
+                decisions.push(DecodingDecision::Replaced {
+                    offset: base + bytes.len() - rest.len() + valid.len(),
+                    len,
+                });
+                rest = &invalid[len..];
+            }
+        }
+    }
+    Decoded {
+        text,
+        encoding: Encoding::Utf8,
+        decisions,
+    }
+}
+
+/// Decode UTF-16 after its byte order mark, replacing unpaired surrogates
+fn decode_utf16(bytes: &[u8], encoding: Encoding) -> Decoded {
+    let mut decisions = vec![DecodingDecision::BomStripped(encoding)];
+    let units = bytes.chunks_exact(2).map(|pair| match encoding {
+        Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+        _ => u16::from_le_bytes([pair[0], pair[1]]),
+    });
+
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut offset = 2;
+    for unit in char::decode_utf16(units) {
+        match unit {
+            Ok(ch) => {
+                text.push(ch);
+                offset += ch.len_utf16() * 2;
+            }
+            Err(_) => {
+                text.push(char::REPLACEMENT_CHARACTER);
+                decisions.push(DecodingDecision::Replaced { offset, len: 2 });
+                offset += 2;
+            }
+        }
+    }
+    if bytes.len() % 2 == 1 {
+        text.push(char::REPLACEMENT_CHARACTER);
+        decisions.push(DecodingDecision::Replaced { offset, len: 1 });
+    }
+
+    Decoded {
+        text,
+        encoding,
+        decisions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8() {
+        let decoded = decode("{\"city\": \"Zürich\"}".as_bytes());
+        assert_eq!(decoded.text, "{\"city\": \"Zürich\"}");
+        assert_eq!(decoded.encoding, Encoding::Utf8);
+        assert!(decoded.decisions.is_empty());
+
+        let decoded = decode(b"\xEF\xBB\xBF[1]");
+        assert_eq!(decoded.text, "[1]");
+        assert_eq!(
+            decoded.decisions,
+            vec![DecodingDecision::BomStripped(Encoding::Utf8)]
+        );
+
+        // Invalid bytes next to real UTF-8 are replaced, including a truncated tail
+        let decoded = decode(b"[\"\xC3\xA9\xFF\", \"\xE2\x82\"]\xE2\x82");
+        assert_eq!(decoded.text, "[\"é\u{FFFD}\", \"\u{FFFD}\"]\u{FFFD}");
+        assert_eq!(
+            decoded.decisions,
+            vec![
+                DecodingDecision::Replaced { offset: 4, len: 1 },
+                DecodingDecision::Replaced { offset: 9, len: 2 },
+                DecodingDecision::Replaced { offset: 13, len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        let mut le = vec![0xFF, 0xFE];
+        let mut be = vec![0xFE, 0xFF];
+        for unit in "[\"é😀\"]".encode_utf16() {
+            le.extend_from_slice(&unit.to_le_bytes());
+            be.extend_from_slice(&unit.to_be_bytes());
+        }
+        for (bytes, encoding) in [(le, Encoding::Utf16Le), (be, Encoding::Utf16Be)] {
+            let decoded = decode(&bytes);
+            assert_eq!(decoded.text, "[\"é😀\"]");
+            assert_eq!(decoded.encoding, encoding);
+            assert_eq!(
+                decoded.decisions,
+                vec![DecodingDecision::BomStripped(encoding)]
+            );
+        }
+
+        // Unpaired surrogate and odd trailing byte
+        let decoded = decode(&[0xFF, 0xFE, b'1', 0, 0x00, 0xD8, b'2', 0, b'3']);
+        assert_eq!(decoded.text, "1\u{FFFD}2\u{FFFD}");
+        assert_eq!(
+            decoded.decisions[1..],
+            [
+                DecodingDecision::Replaced { offset: 4, len: 2 },
+                DecodingDecision::Replaced { offset: 8, len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_windows_1252() {
+        let decoded = decode(b"{\"name\": \"Jos\xE9\", \"quote\": \"\x93hi\x94 \x80\"}");
+        assert_eq!(
+            decoded.text,
+            "{\"name\": \"José\", \"quote\": \"\u{201C}hi\u{201D} €\"}"
+        );
+        assert_eq!(decoded.encoding, Encoding::Windows1252);
+        assert_eq!(
+            decoded.decisions,
+            vec![DecodingDecision::Windows1252Fallback]
+        );
+    }
+}
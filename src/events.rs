@@ -0,0 +1,160 @@
+//! Pull-based events over broken JSON
+//!
+//! The events come from the same state machine as [`repair_json`](crate::repair_json),
+//! one container member at a time, without building the repaired JSON.
+
+use crate::input::Input;
+use crate::parser::JsonRepairParser;
+use crate::{JsonRepairError, Repair, RepairOptions};
+use serde_json::Number;
+use std::io::Read;
+
+/// A piece of the repaired document, or a fix applied to the input
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// Key of the next object member
+    Key(String),
+    String(String),
+    Number(Number),
+    Bool(bool),
+    Null,
+    /// A fix applied to the input, before the events of the value it affects
+    Repair(Repair),
+}
+
+/// Iterator over the events of broken JSON, see [`parse_events`] and [`read_events`]
+///
+/// Reading stops at the first error, which is the last item.
+pub struct Events<'a> {
+    parser: JsonRepairParser<'a>,
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<Event, JsonRepairError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parser.next_event()
+    }
+}
+
+/// Iterate over the events of a broken JSON string
+///
+/// # Arguments
+///
+/// * `json_str` - The broken JSON string to read
+/// * `options` - Configuration options for the repair process
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{parse_events, Event, RepairOptions};
+///
+/// let events: Vec<Event> = parse_events("[{id: 1}, {id: 2", &RepairOptions::default())
+///     .filter_map(Result::ok)
+///     .filter(|event| !matches!(event, Event::Repair(_)))
+///     .collect();
+/// assert_eq!(events[..4], [Event::StartArray, Event::StartObject, Event::Key("id".to_string()), Event::Number(1.into())]);
+///
+/// // Count the records without building them
+/// let records = events.iter().filter(|event| **event == Event::StartObject).count();
+/// assert_eq!(records, 2);
+/// ```
+pub fn parse_events<'a>(json_str: &'a str, options: &RepairOptions) -> Events<'a> {
+    Events {
+        parser: JsonRepairParser::event_parser(Input::from_str(json_str), options.clone()),
+    }
+}
+
+/// Iterate over the events of broken JSON read from `reader`
+///
+/// The reader is read through a bounded window, so memory stays bounded by the nesting
+/// depth and the longest string, whatever the size of the input.
+///
+/// # Arguments
+///
+/// * `reader` - A reader containing JSON data
+/// * `options` - Configuration options for the repair process
+pub fn read_events<'a, R: Read + 'a>(reader: R, options: &RepairOptions) -> Events<'a> {
+    Events {
+        parser: JsonRepairParser::event_parser(Input::from_reader(reader), options.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RepairKind;
+
+    #[test]
+    fn test_events() {
+        let options = RepairOptions::default();
+        let events: Vec<Event> = parse_events("{a: [true, None, 'x', -1.5e3], \"b\": {}", &options)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let repair = |event: &Event| match event {
+            Event::Repair(repair) => Some((repair.kind, repair.path.clone())),
+            _ => None,
+        };
+        assert_eq!(
+            events.iter().filter_map(repair).collect::<Vec<_>>(),
+            vec![
+                (RepairKind::QuotedKey, "/a".to_string()),
+                (RepairKind::NormalizedLiteral, "/a/1".to_string()),
+                (RepairKind::ReplacedQuotes, "/a/2".to_string()),
+                (RepairKind::ClosedObject, String::new()),
+            ]
+        );
+        assert_eq!(
+            events
+                .into_iter()
+                .filter(|event| repair(event).is_none())
+                .collect::<Vec<_>>(),
+            vec![
+                Event::StartObject,
+                Event::Key("a".to_string()),
+                Event::StartArray,
+                Event::Bool(true),
+                Event::Null,
+                Event::String("x".to_string()),
+                Event::Number(Number::from_f64(-1500.0).unwrap()),
+                Event::EndArray,
+                Event::Key("b".to_string()),
+                Event::StartObject,
+                Event::EndObject,
+                Event::EndObject,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_events() {
+        // Far more records than the reader window holds, cut off mid-record
+        let mut input = String::from("[");
+        for i in 0..5000 {
+            input.push_str(&format!("{{\"id\": {}, \"name\": 'n{}'}},\n", i, i));
+        }
+        input.push_str("{\"id\": 5000, \"na");
+        let mut records = 0;
+        let mut last_name = None;
+        for event in read_events(input.as_bytes(), &RepairOptions::default()) {
+            match event.unwrap() {
+                Event::StartObject => records += 1,
+                Event::String(name) => last_name = Some(name),
+                _ => {}
+            }
+        }
+        assert_eq!(records, 5001);
+        assert_eq!(last_name.as_deref(), Some("n4999"));
+
+        // Nothing but whitespace becomes an empty object
+        let events: Vec<Event> = parse_events(" \n ", &RepairOptions::default())
+            .map(Result::unwrap)
+            .filter(|event| !matches!(event, Event::Repair(_)))
+            .collect();
+        assert_eq!(events, vec![Event::StartObject, Event::EndObject]);
+    }
+}
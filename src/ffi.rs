@@ -0,0 +1,522 @@
+//! C API, enabled with the `ffi` feature
+//!
+//! The crate builds as a `cdylib` exposing these functions; their declarations are in
+//! `include/llm_json.h`, generated with `cbindgen --config cbindgen.toml --output
+//! include/llm_json.h`.
+//!
+//! Strings returned through `out` and error messages are owned by the library and must
+//! be released with [`llm_json_free`].
+
+use crate::{
+    JsonRepairError, NumberOptions, NumberRepair, Placeholders, RepairOptions, RootValues, loads,
+    repair_json,
+};
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Outcome of a call, mirroring [`JsonRepairError`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmJsonStatus {
+    Ok = 0,
+    UnrepairableJson = 1,
+    IoError = 2,
+    SerdeError = 3,
+    Utf8Error = 4,
+    /// A required pointer was null
+    InvalidArgument = 5,
+    /// The library panicked, which is a bug
+    Panic = 6,
+    /// A number was written in a notation the options reject
+    InvalidNumber = 7,
+    /// The input holds several top-level values and the options allow one
+    MultipleValues = 8,
+    /// The model elided content with a placeholder the options reject
+    ElidedContent = 9,
+}
+
+/// Error details filled in by failing calls
+#[repr(C)]
+#[derive(Debug)]
+pub struct LlmJsonError {
+    pub code: LlmJsonStatus,
+    /// Human-readable description, null on success
+    pub message: *mut c_char,
+}
+
+/// Options for a call, see [`RepairOptions`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LlmJsonOptions {
+    pub skip_json_loads: bool,
+    pub ensure_ascii: bool,
+    pub stream_stable: bool,
+    pub skip_reasoning: bool,
+    /// Tag names of reasoning sections as NUL-terminated strings, null for the defaults
+    pub reasoning_tags: *const *const c_char,
+    pub reasoning_tags_len: usize,
+    pub numbers: LlmJsonNumberOptions,
+    /// One of [`LlmJsonRootValues`]
+    pub root_values: c_int,
+    pub index_maps: bool,
+    /// One of [`LlmJsonPlaceholders`]
+    pub placeholders: c_int,
+}
+
+/// How a notation of numbers is repaired, see [`NumberRepair`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmJsonNumberRepair {
+    Number,
+    String,
+    Error,
+}
+
+/// How each notation of numbers is repaired, see [`NumberOptions`]
+///
+/// Each field is one of [`LlmJsonNumberRepair`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LlmJsonNumberOptions {
+    pub leading_zeros: c_int,
+    pub plus_sign: c_int,
+    pub bare_decimal_point: c_int,
+    pub thousands_separators: c_int,
+    pub decimal_comma: c_int,
+    pub percent: c_int,
+    pub currency: c_int,
+    pub magnitude_suffix: c_int,
+    pub unit: c_int,
+}
+
+/// What to do with several top-level values, see [`RootValues`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmJsonRootValues {
+    First,
+    Last,
+    Wrap,
+    Error,
+}
+
+/// What to do with placeholders for elided content, see [`Placeholders`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmJsonPlaceholders {
+    Keep,
+    Drop,
+    Null,
+    Error,
+}
+
+/// Conversions both ways between the value of a C enum and the enum of the crate it
+/// mirrors
+///
+/// Values come from C as plain integers, as one outside the enum would be undefined
+/// behavior in a Rust enum; they are checked when converted.
+macro_rules! mirror {
+    ($c:ident, $rust:ident, [$($variant:ident),*]) => {
+        impl From<$rust> for c_int {
+            fn from(value: $rust) -> Self {
+                match value {
+                    $($rust::$variant => $c::$variant as c_int,)*
+                }
+            }
+        }
+
+        impl TryFrom<c_int> for $rust {
+            type Error = (LlmJsonStatus, String);
+
+            fn try_from(value: c_int) -> Result<Self, (LlmJsonStatus, String)> {
+                match value {
+                    $(value if value == $c::$variant as c_int => Ok($rust::$variant),)*
+                    _ => Err((
+                        LlmJsonStatus::InvalidArgument,
+                        format!("{} is not a valid {}", value, stringify!($c)),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+mirror!(LlmJsonNumberRepair, NumberRepair, [Number, String, Error]);
+mirror!(LlmJsonRootValues, RootValues, [First, Last, Wrap, Error]);
+mirror!(LlmJsonPlaceholders, Placeholders, [Keep, Drop, Null, Error]);
+
+impl From<NumberOptions> for LlmJsonNumberOptions {
+    fn from(options: NumberOptions) -> Self {
+        Self {
+            leading_zeros: options.leading_zeros.into(),
+            plus_sign: options.plus_sign.into(),
+            bare_decimal_point: options.bare_decimal_point.into(),
+            thousands_separators: options.thousands_separators.into(),
+            decimal_comma: options.decimal_comma.into(),
+            percent: options.percent.into(),
+            currency: options.currency.into(),
+            magnitude_suffix: options.magnitude_suffix.into(),
+            unit: options.unit.into(),
+        }
+    }
+}
+
+impl TryFrom<LlmJsonNumberOptions> for NumberOptions {
+    type Error = (LlmJsonStatus, String);
+
+    fn try_from(options: LlmJsonNumberOptions) -> Result<Self, Self::Error> {
+        Ok(Self {
+            leading_zeros: options.leading_zeros.try_into()?,
+            plus_sign: options.plus_sign.try_into()?,
+            bare_decimal_point: options.bare_decimal_point.try_into()?,
+            thousands_separators: options.thousands_separators.try_into()?,
+            decimal_comma: options.decimal_comma.try_into()?,
+            percent: options.percent.try_into()?,
+            currency: options.currency.try_into()?,
+            magnitude_suffix: options.magnitude_suffix.try_into()?,
+            unit: options.unit.try_into()?,
+        })
+    }
+}
+
+/// The default options
+#[unsafe(no_mangle)]
+pub extern "C" fn llm_json_default_options() -> LlmJsonOptions {
+    let defaults = RepairOptions::default();
+    LlmJsonOptions {
+        skip_json_loads: defaults.skip_json_loads,
+        ensure_ascii: defaults.ensure_ascii,
+        stream_stable: defaults.stream_stable,
+        skip_reasoning: defaults.skip_reasoning,
+        reasoning_tags: ptr::null(),
+        reasoning_tags_len: 0,
+        numbers: defaults.numbers.into(),
+        root_values: defaults.root_values.into(),
+        index_maps: defaults.index_maps,
+        placeholders: defaults.placeholders.into(),
+    }
+}
+
+/// Repair the `len` bytes of UTF-8 at `input`
+///
+/// On success `*out` receives the repaired JSON as a NUL-terminated string. On failure
+/// `*out` is set to null and `err`, if not null, receives the error. `options` may be
+/// null for the defaults.
+///
+/// # Safety
+///
+/// `input` must point to `len` readable bytes, `out` must be a valid pointer, and
+/// `options` and `err` must be null or valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn llm_json_repair(
+    input: *const c_char,
+    len: usize,
+    options: *const LlmJsonOptions,
+    out: *mut *mut c_char,
+    err: *mut LlmJsonError,
+) -> LlmJsonStatus {
+    // SAFETY: the caller upholds the same contract
+    unsafe { call(input, len, options, out, err, repair_json) }
+}
+
+/// Repair the `len` bytes of UTF-8 at `input` and validate the result
+///
+/// Works like [`llm_json_repair`], except that the output is always validated, as with
+/// `loads`, even when `skip_json_loads` is set.
+///
+/// # Safety
+///
+/// Same as [`llm_json_repair`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn llm_json_loads(
+    input: *const c_char,
+    len: usize,
+    options: *const LlmJsonOptions,
+    out: *mut *mut c_char,
+    err: *mut LlmJsonError,
+) -> LlmJsonStatus {
+    // SAFETY: the caller upholds the same contract
+    unsafe {
+        call(input, len, options, out, err, |text, options| {
+            Ok(loads(text, options)?.to_string())
+        })
+    }
+}
+
+/// Release a string returned by this library; null is ignored
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this library that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn llm_json_free(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: the string was created by `CString::into_raw`
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+unsafe fn call(
+    input: *const c_char,
+    len: usize,
+    options: *const LlmJsonOptions,
+    out: *mut *mut c_char,
+    err: *mut LlmJsonError,
+    repair: impl Fn(&str, &RepairOptions) -> Result<String, JsonRepairError>,
+) -> LlmJsonStatus {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        if out.is_null() || (input.is_null() && len > 0) {
+            return Err((LlmJsonStatus::InvalidArgument, "null pointer".to_string()));
+        }
+        let bytes = if len == 0 {
+            &[][..]
+        } else {
+            // SAFETY: the caller guarantees `len` readable bytes
+            unsafe { std::slice::from_raw_parts(input.cast::<u8>(), len) }
+        };
+        // SAFETY: the caller guarantees `options` is null or valid
+        let options = unsafe { repair_options(options) }?;
+        let text = std::str::from_utf8(bytes).map_err(JsonRepairError::from)?;
+        let repaired = repair(text, &options)?;
+        CString::new(repaired).map_err(|_| {
+            (
+                LlmJsonStatus::UnrepairableJson,
+                "repaired JSON contains a NUL byte".to_string(),
+            )
+        })
+    }));
+
+    let (status, message) = match result {
+        Ok(Ok(repaired)) => {
+            // SAFETY: `out` was checked for null
+            unsafe { *out = repaired.into_raw() };
+            (LlmJsonStatus::Ok, None)
+        }
+        Ok(Err((status, message))) => (status, Some(message)),
+        Err(_) => (
+            LlmJsonStatus::Panic,
+            Some("panic while repairing".to_string()),
+        ),
+    };
+    if status != LlmJsonStatus::Ok && !out.is_null() {
+        // SAFETY: `out` was checked for null
+        unsafe { *out = ptr::null_mut() };
+    }
+    if !err.is_null() {
+        let message = message.map_or(ptr::null_mut(), |message| {
+            CString::new(message.replace('\0', ""))
+                .expect("NUL bytes were removed")
+                .into_raw()
+        });
+        // SAFETY: the caller guarantees `err` is null or valid
+        unsafe {
+            *err = LlmJsonError {
+                code: status,
+                message,
+            }
+        };
+    }
+    status
+}
+
+impl From<JsonRepairError> for (LlmJsonStatus, String) {
+    fn from(e: JsonRepairError) -> Self {
+        let status = match e {
+            JsonRepairError::UnrepairableJson => LlmJsonStatus::UnrepairableJson,
+            JsonRepairError::InvalidNumber { .. } => LlmJsonStatus::InvalidNumber,
+            JsonRepairError::MultipleValues { .. } => LlmJsonStatus::MultipleValues,
+            JsonRepairError::ElidedContent { .. } => LlmJsonStatus::ElidedContent,
+            JsonRepairError::IoError(_) => LlmJsonStatus::IoError,
+            JsonRepairError::SerdeError(_) | JsonRepairError::SerdeErrorAt { .. } => {
+                LlmJsonStatus::SerdeError
+            }
+            JsonRepairError::Utf8Error(_) => LlmJsonStatus::Utf8Error,
+        };
+        (status, e.to_string())
+    }
+}
+
+unsafe fn repair_options(
+    options: *const LlmJsonOptions,
+) -> Result<RepairOptions, (LlmJsonStatus, String)> {
+    let mut repair_options = RepairOptions::default();
+    // SAFETY: the caller guarantees `options` is null or valid
+    let Some(options) = (unsafe { options.as_ref() }) else {
+        return Ok(repair_options);
+    };
+    repair_options.skip_json_loads = options.skip_json_loads;
+    repair_options.ensure_ascii = options.ensure_ascii;
+    repair_options.stream_stable = options.stream_stable;
+    repair_options.skip_reasoning = options.skip_reasoning;
+    repair_options.numbers = options.numbers.try_into()?;
+    repair_options.root_values = options.root_values.try_into()?;
+    repair_options.index_maps = options.index_maps;
+    repair_options.placeholders = options.placeholders.try_into()?;
+    if !options.reasoning_tags.is_null() {
+        // SAFETY: the caller guarantees `reasoning_tags_len` valid strings
+        let tags = unsafe {
+            std::slice::from_raw_parts(options.reasoning_tags, options.reasoning_tags_len)
+        };
+        repair_options.reasoning_tags = tags
+            .iter()
+            .map(|&tag| {
+                if tag.is_null() {
+                    return Err((LlmJsonStatus::InvalidArgument, "null tag".to_string()));
+                }
+                // SAFETY: the tag is a NUL-terminated string
+                let tag = unsafe { CStr::from_ptr(tag) };
+                Ok(tag.to_string_lossy().into_owned())
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(repair_options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Call `function` on `input` and take ownership of what it returned
+    fn run(
+        function: unsafe extern "C" fn(
+            *const c_char,
+            usize,
+            *const LlmJsonOptions,
+            *mut *mut c_char,
+            *mut LlmJsonError,
+        ) -> LlmJsonStatus,
+        input: &[u8],
+        options: Option<&LlmJsonOptions>,
+    ) -> (LlmJsonStatus, Option<String>, Option<String>) {
+        let mut out = ptr::null_mut();
+        let mut err = LlmJsonError {
+            code: LlmJsonStatus::Ok,
+            message: ptr::null_mut(),
+        };
+        let options = options.map_or(ptr::null(), |options| options as *const _);
+        let status = unsafe {
+            function(
+                input.as_ptr().cast(),
+                input.len(),
+                options,
+                &mut out,
+                &mut err,
+            )
+        };
+        assert_eq!(status, err.code);
+        let take = |string: *mut c_char| {
+            (!string.is_null()).then(|| {
+                let owned = unsafe { CStr::from_ptr(string) }
+                    .to_string_lossy()
+                    .into_owned();
+                unsafe { llm_json_free(string) };
+                owned
+            })
+        };
+        (status, take(out), take(err.message))
+    }
+
+    #[test]
+    fn test_repair() {
+        let (status, out, message) = run(llm_json_repair, b"{name: 'John', age: 30,", None);
+        assert_eq!(status, LlmJsonStatus::Ok);
+        assert_eq!(out.as_deref(), Some(r#"{"age":30,"name":"John"}"#));
+        assert_eq!(message, None);
+
+        let tags = [c"plan".as_ptr()];
+        let options = LlmJsonOptions {
+            skip_json_loads: true,
+            skip_reasoning: true,
+            reasoning_tags: tags.as_ptr(),
+            reasoning_tags_len: tags.len(),
+            ..llm_json_default_options()
+        };
+        let (_, out, _) = run(
+            llm_json_repair,
+            b"<plan>[0]</plan>{b: 1, a: 2",
+            Some(&options),
+        );
+        assert_eq!(out.as_deref(), Some(r#"{"b":1,"a":2}"#));
+
+        let (status, out, _) = run(llm_json_loads, b"[1, 2", Some(&options));
+        assert_eq!(status, LlmJsonStatus::Ok);
+        assert_eq!(out.as_deref(), Some("[1,2]"));
+
+        let options = LlmJsonOptions {
+            numbers: LlmJsonNumberOptions {
+                percent: LlmJsonNumberRepair::Number as c_int,
+                ..llm_json_default_options().numbers
+            },
+            root_values: LlmJsonRootValues::Wrap as c_int,
+            placeholders: LlmJsonPlaceholders::Drop as c_int,
+            ..llm_json_default_options()
+        };
+        let (_, out, _) = run(llm_json_repair, b"[50%, ...] [2]", Some(&options));
+        assert_eq!(out.as_deref(), Some("[[0.5],[2]]"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let (status, out, message) = run(llm_json_repair, b"[\"\xff\"]", None);
+        assert_eq!(status, LlmJsonStatus::Utf8Error);
+        assert_eq!(out, None);
+        assert_eq!(message.as_deref(), Some("Invalid UTF-8 in input"));
+
+        // Each rejection has its own status
+        let options = LlmJsonOptions {
+            numbers: LlmJsonNumberOptions {
+                unit: LlmJsonNumberRepair::Error as c_int,
+                ..llm_json_default_options().numbers
+            },
+            root_values: LlmJsonRootValues::Error as c_int,
+            placeholders: LlmJsonPlaceholders::Error as c_int,
+            ..llm_json_default_options()
+        };
+        for (input, expected) in [
+            (&b"[5 kg]"[..], LlmJsonStatus::InvalidNumber),
+            (b"[1] [2]", LlmJsonStatus::MultipleValues),
+            (b"[1, ...]", LlmJsonStatus::ElidedContent),
+        ] {
+            assert_eq!(run(llm_json_repair, input, Some(&options)).0, expected);
+        }
+
+        // Values outside an enum are rejected rather than read
+        let options = LlmJsonOptions {
+            root_values: 7,
+            ..llm_json_default_options()
+        };
+        let (status, _, message) = run(llm_json_repair, b"[1]", Some(&options));
+        assert_eq!(status, LlmJsonStatus::InvalidArgument);
+        assert_eq!(
+            message.as_deref(),
+            Some("7 is not a valid LlmJsonRootValues")
+        );
+
+        let status = unsafe {
+            llm_json_repair(
+                c"[1".as_ptr(),
+                2,
+                ptr::null(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, LlmJsonStatus::InvalidArgument);
+
+        // Empty input needs no pointer
+        let mut out = ptr::null_mut();
+        let status =
+            unsafe { llm_json_repair(ptr::null(), 0, ptr::null(), &mut out, ptr::null_mut()) };
+        assert_eq!(status, LlmJsonStatus::Ok);
+        unsafe { llm_json_free(out) };
+
+        // A NUL after a backslash is escaped even without validation
+        let options = LlmJsonOptions {
+            skip_json_loads: true,
+            ..llm_json_default_options()
+        };
+        let (status, out, _) = run(llm_json_repair, b"[\"a\\\0\"]", Some(&options));
+        assert_eq!(status, LlmJsonStatus::Ok);
+        assert_eq!(out.as_deref(), Some(r#"["a\\\u0000"]"#));
+    }
+}
@@ -0,0 +1,217 @@
+//! Input buffering for the repair parser
+
+use crate::JsonRepairError;
+use std::io::{ErrorKind, Read};
+
+/// Bytes requested from a reader at a time
+const READ_CHUNK: usize = 8 * 1024;
+/// Consumed bytes kept in the window before it is compacted
+const RETAIN: usize = 64 * 1024;
+/// How far ahead reader input is buffered when searching for markers
+pub(crate) const LOOKAHEAD: usize = 1024 * 1024;
+
+/// Text the parser reads from, addressed by absolute byte offsets
+///
+/// In-memory input holds the whole document. Reader input holds a window that is
+/// refilled on demand and trimmed behind the parser, so memory stays bounded.
+pub(crate) struct Input<'a> {
+    buffer: String,
+    /// Absolute offset of the first byte in `buffer`
+    base: usize,
+    /// Absolute offset where the input is cut short (e.g. a closing markdown fence)
+    limit: Option<usize>,
+    /// Absolute offset up to which characters can be read without refilling
+    end: usize,
+    source: Option<Source<'a>>,
+}
+
+struct Source<'a> {
+    reader: Box<dyn Read + 'a>,
+    /// Bytes of a character split across reads
+    pending: Vec<u8>,
+    eof: bool,
+    error: Option<JsonRepairError>,
+}
+
+impl<'a> Input<'a> {
+    pub(crate) fn from_str(text: &str) -> Self {
+        Self {
+            buffer: text.to_string(),
+            base: 0,
+            limit: None,
+            end: text.len(),
+            source: None,
+        }
+    }
+
+    pub(crate) fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        Self {
+            buffer: String::new(),
+            base: 0,
+            limit: None,
+            end: 0,
+            source: Some(Source {
+                reader: Box::new(reader),
+                pending: Vec::new(),
+                eof: false,
+                error: None,
+            }),
+        }
+    }
+
+    /// Whether the input is a window over a reader rather than a whole document
+    pub(crate) fn is_streaming(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// The character starting at `offset`, `None` past the end of the input
+    #[inline]
+    pub(crate) fn char_at(&mut self, offset: usize) -> Option<char> {
+        // Fast path for buffered ASCII, which is complete on its own
+        if offset < self.end
+            && let Some(&byte) = self.buffer.as_bytes().get(offset.wrapping_sub(self.base))
+            && byte.is_ascii()
+        {
+            return Some(byte as char);
+        }
+        self.char_at_slow(offset)
+    }
+
+    fn char_at_slow(&mut self, offset: usize) -> Option<char> {
+        if self.limit.is_some_and(|limit| offset >= limit) {
+            return None;
+        }
+        self.fill(offset + 4);
+        let start = offset.checked_sub(self.base)?;
+        self.buffer.get(start..)?.chars().next()
+    }
+
+    /// Whether the input continues with `pattern` at `offset`
+    pub(crate) fn starts_with(&mut self, offset: usize, pattern: &str, ignore_case: bool) -> bool {
+        let end = offset + pattern.len();
+        if self.limit.is_some_and(|limit| end > limit) {
+            return false;
+        }
+        self.fill(end);
+        let Some(start) = offset.checked_sub(self.base) else {
+            return false;
+        };
+        match self.buffer.as_bytes().get(start..end - self.base) {
+            Some(bytes) if ignore_case => bytes.eq_ignore_ascii_case(pattern.as_bytes()),
+            Some(bytes) => bytes == pattern.as_bytes(),
+            None => false,
+        }
+    }
+
+    /// Offset of the first `pattern` at or after `offset`
+    ///
+    /// Reader input is only searched up to [`LOOKAHEAD`] bytes ahead.
+    pub(crate) fn find(
+        &mut self,
+        offset: usize,
+        pattern: &str,
+        ignore_case: bool,
+    ) -> Option<usize> {
+        self.fill(offset.saturating_add(LOOKAHEAD));
+        let start = offset.checked_sub(self.base)?;
+        let end = self
+            .limit
+            .map_or(self.buffer.len(), |limit| limit.saturating_sub(self.base))
+            .min(self.buffer.len());
+        let haystack = self.buffer.as_bytes().get(start..end)?;
+        let needle = pattern.as_bytes();
+        haystack
+            .windows(needle.len())
+            .position(|window| {
+                if ignore_case {
+                    window.eq_ignore_ascii_case(needle)
+                } else {
+                    window == needle
+                }
+            })
+            .map(|position| offset + position)
+    }
+
+    /// Offset of the last `pattern` in the input, only known for in-memory input
+    pub(crate) fn rfind(&self, pattern: &str) -> Option<usize> {
+        if self.is_streaming() {
+            return None;
+        }
+        self.buffer.rfind(pattern)
+    }
+
+    /// Treat everything from `offset` on as past the end of the input
+    pub(crate) fn set_limit(&mut self, offset: usize) {
+        self.limit = Some(offset);
+        self.update_end();
+    }
+
+    fn update_end(&mut self) {
+        let buffered = self.base + self.buffer.len();
+        self.end = self.limit.map_or(buffered, |limit| limit.min(buffered));
+    }
+
+    /// Let the window drop everything before `offset`, which the parser will not revisit
+    pub(crate) fn release(&mut self, offset: usize) {
+        if self.source.is_none() {
+            return;
+        }
+        let consumed = offset.saturating_sub(self.base);
+        if consumed > RETAIN && consumed <= self.buffer.len() {
+            self.buffer.drain(..consumed);
+            self.base = offset;
+            self.update_end();
+        }
+    }
+
+    /// The first error hit while reading, reading stops there as if the input ended
+    pub(crate) fn take_error(&mut self) -> Option<JsonRepairError> {
+        self.source.as_mut().and_then(|source| source.error.take())
+    }
+
+    /// Read from the source until `end` is buffered or the source is exhausted
+    #[inline(never)]
+    fn fill(&mut self, end: usize) {
+        let Some(source) = &mut self.source else {
+            return;
+        };
+        if self.base + self.buffer.len() >= end {
+            return;
+        }
+        let mut chunk = [0u8; READ_CHUNK];
+        while self.base + self.buffer.len() < end && !source.eof {
+            match source.reader.read(&mut chunk) {
+                Ok(0) => {
+                    source.eof = true;
+                    if let Err(e) = std::str::from_utf8(&source.pending) {
+                        source.error = Some(e.into());
+                    }
+                }
+                Ok(read) => {
+                    source.pending.extend_from_slice(&chunk[..read]);
+                    let valid = match std::str::from_utf8(&source.pending) {
+                        Ok(text) => text.len(),
+                        // The last character continues in the next read
+                        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                        Err(e) => {
+                            source.error = Some(e.into());
+                            source.eof = true;
+                            e.valid_up_to()
+                        }
+                    };
+                    let text = std::str::from_utf8(&source.pending[..valid])
+                        .expect("prefix was validated");
+                    self.buffer.push_str(text);
+                    source.pending.drain(..valid);
+                    let buffered = self.base + self.buffer.len();
+                    self.end = self.limit.map_or(buffered, |limit| limit.min(buffered));
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    source.error = Some(e.into());
+                    source.eof = true;
+                }
+            }
+        }
+    }
+}
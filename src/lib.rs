@@ -10,11 +10,27 @@
 //! ## Features
 //!
 //! - Fix missing quotes around keys and values
-//! - Handle misplaced commas and missing brackets
+//! - Handle misplaced commas and missing brackets, opening ones included
 //! - Repair incomplete arrays and objects
+//! - Read key/value pairs in `[]` as objects and bare values in `{}` as arrays
 //! - Remove extra non-JSON characters
+//! - Keep the first or last of several top-level values, or wrap them into an array
 //! - Auto-complete missing values with sensible defaults
+//! - Optionally drop, null out or reject placeholders such as `...` for elided content
+//! - Normalize numbers such as `007`, `+5`, `1,000,000` or `3,14`, with a policy per notation
 //! - Preserve Unicode characters
+//! - Optionally ignore reasoning sections such as `<think>...</think>`
+//! - Serde helpers for fields holding broken JSON (see [`serde`])
+//! - Async readers and byte streams with the `async` feature
+//! - Bounded-memory repair from a reader to a writer
+//! - Report of every repair applied, with its kind, position and a confidence score
+//! - Minimal-edit repair that keeps the original formatting, also as text edits
+//! - Source map from each value of the repaired JSON to the input it came from
+//! - Pull-based events over broken input, without building the repaired JSON
+//! - Callbacks when values at chosen paths complete while the input streams in
+//! - Byte input in UTF-8, UTF-16 or Windows-1252, with every decoding decision reported
+//! - C API with the `ffi` feature (see [`ffi`])
+//! - Python bindings compatible with json_repair, with the `python` feature
 //!
 //! ## Usage
 //!
@@ -30,14 +46,45 @@
 //! let value = loads(broken_json, &Default::default()).unwrap();
 //! ```
 
+#[cfg(feature = "async")]
+mod async_io;
+mod edit;
+mod encoding;
+mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod input;
+mod number;
+mod parser;
+#[cfg(feature = "python")]
+mod python;
+mod report;
+pub mod serde;
+mod source_map;
+mod watch;
+
+#[cfg(feature = "async")]
+pub use async_io::{RepairStream, load_async, load_stream, repair_stream};
+pub use edit::{TextEdit, apply_edits, repair_json_edits};
+pub use encoding::{DecodingDecision, Encoding};
+pub use events::{Event, Events, parse_events, read_events};
+pub use number::{NumberOptions, NumberRepair};
+pub use report::{Repair, RepairKind, RepairReport, confidence};
+pub use source_map::SourceMap;
+pub use watch::PathWatcher;
+
+use parser::JsonRepairParser;
 use serde_json::Value;
+use source_map::Span;
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::Path;
 use thiserror::Error;
 
 /// Errors that can occur during JSON repair
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum JsonRepairError {
     #[error("JSON string is too broken to repair")]
     UnrepairableJson,
@@ -47,6 +94,51 @@ pub enum JsonRepairError {
     SerdeError(#[from] serde_json::Error),
     #[error("Invalid UTF-8 in input")]
     Utf8Error(#[from] std::str::Utf8Error),
+    /// Validation of the repaired JSON failed at a value that came from `span` of the
+    /// input, reported with the `source_map` option
+    #[error("Serde JSON error: {error}, in input bytes {}..{}", span.start, span.end)]
+    SerdeErrorAt {
+        error: serde_json::Error,
+        span: Range<usize>,
+    },
+    /// A number was written in a notation that [`NumberOptions`] rejects
+    #[error("Number `{text}` at byte {offset} is written in a rejected notation")]
+    InvalidNumber { text: String, offset: usize },
+    /// The input holds another top-level value at `offset`, which [`RootValues`] rejects
+    #[error("Another top-level value starts at byte {offset}")]
+    MultipleValues { offset: usize },
+    /// The model elided content with a placeholder at `offset`, which [`Placeholders`]
+    /// rejects
+    #[error("The model elided content with a placeholder at byte {offset}")]
+    ElidedContent { offset: usize },
+}
+
+impl JsonRepairError {
+    /// A short message telling the model its output could not be used, to send back
+    /// when asking it again
+    ///
+    /// For output that was repaired, [`RepairReport::feedback`] tells what was wrong.
+    pub fn feedback(&self) -> String {
+        let reason = match self {
+            JsonRepairError::SerdeError(error) | JsonRepairError::SerdeErrorAt { error, .. } => {
+                format!("was not valid JSON ({})", error)
+            }
+            JsonRepairError::Utf8Error(_) => "was not valid UTF-8 text".to_string(),
+            JsonRepairError::InvalidNumber { text, .. } => {
+                format!("had a number written as `{}`", text)
+            }
+            JsonRepairError::MultipleValues { .. } => {
+                "held several JSON values where one was expected".to_string()
+            }
+            JsonRepairError::ElidedContent { .. } => {
+                "left content out with a placeholder such as `...`".to_string()
+            }
+            JsonRepairError::UnrepairableJson | JsonRepairError::IoError(_) => {
+                "could not be read as JSON".to_string()
+            }
+        };
+        format!("Your response {}; respond with valid JSON only.", reason)
+    }
 }
 
 /// Configuration options for JSON repair
@@ -60,6 +152,68 @@ pub struct RepairOptions {
     pub ensure_ascii: bool,
     /// Handle streaming/incomplete JSON
     pub stream_stable: bool,
+    /// Ignore reasoning sections (e.g. `<think>...</think>`) when looking for JSON
+    pub skip_reasoning: bool,
+    /// Tag names that delimit reasoning sections, matched case-insensitively
+    pub reasoning_tags: Vec<String>,
+    /// Record where each value came from in the input, see [`RepairReport::source_map`]
+    pub source_map: bool,
+    /// How numbers written the way people write them are repaired
+    pub numbers: NumberOptions,
+    /// What to do when the input holds several top-level values
+    pub root_values: RootValues,
+    /// Turn objects keyed `"0"`, `"1"`, ... like `{"0": "a", "1": "b"}` into arrays
+    ///
+    /// The keys are read from the validated value, so this does nothing with
+    /// `skip_json_loads`, for minimal repairs or when streaming.
+    pub index_maps: bool,
+    /// What to do with placeholders such as `...` for content the model left out
+    pub placeholders: Placeholders,
+}
+
+/// What to do when the input holds several top-level objects or arrays, as in
+/// `{"a": 1}{"a": 2}`
+///
+/// Values count as one sequence when only whitespace, commas or markdown code fences
+/// separate them, and an array only when it starts like JSON rather than like prose in
+/// brackets, as in `[see note]`. Other text after the first value is skipped as before.
+///
+/// Streamed repairs, such as [`repair_to_writer`] and [`read_events`], cannot go back to
+/// wrap or drop values they already produced: there, `Wrap` and `Last` fail with
+/// [`JsonRepairError::MultipleValues`] when a second value shows up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootValues {
+    /// Keep the first value, reporting the others with [`RepairKind::DroppedValue`]
+    #[default]
+    First,
+    /// Keep the last value, reporting the others with [`RepairKind::DroppedValue`]
+    Last,
+    /// Wrap all the values into an array, reporting each with
+    /// [`RepairKind::WrappedValue`]
+    Wrap,
+    /// Fail with [`JsonRepairError::MultipleValues`]
+    Error,
+}
+
+/// What to do with placeholders a model writes for content it left out, as in
+/// `[1, 2, 3, ...]` or `{"a": 1, …}`
+///
+/// Three or more dots, or `…`, count when they stand alone as a member or value;
+/// `"..."` in quotes is a string. So does a comment standing alone there, as in
+/// `[ /* more */ ]`, which is otherwise removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Placeholders {
+    /// Keep them as the string `"..."`
+    #[default]
+    Keep,
+    /// Drop them, reporting [`RepairKind::DroppedPlaceholder`]; one standing for the
+    /// value of a key becomes `null`
+    Drop,
+    /// Replace them with `null`, reporting [`RepairKind::ReplacedPlaceholder`]; one
+    /// standing for members of an object is dropped
+    Null,
+    /// Fail with [`JsonRepairError::ElidedContent`]
+    Error,
 }
 
 impl Default for RepairOptions {
@@ -69,548 +223,38 @@ impl Default for RepairOptions {
             return_objects: false,
             ensure_ascii: true,
             stream_stable: false,
+            skip_reasoning: false,
+            reasoning_tags: vec![
+                "think".to_string(),
+                "thinking".to_string(),
+                "reasoning".to_string(),
+            ],
+            source_map: false,
+            numbers: NumberOptions::default(),
+            root_values: RootValues::default(),
+            index_maps: false,
+            placeholders: Placeholders::default(),
         }
     }
 }
 
-/// Internal parser state for tracking context
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum ParseState {
-    Root,
-    Object,
-    Array,
-}
-
-/// JSON repair parser
-struct JsonRepairParser {
-    input: Vec<char>,
-    pos: usize,
-    output: String,
-    state_stack: Vec<ParseState>,
-    options: RepairOptions,
+/// JSON repaired from bytes, with how the bytes were decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairedBytes {
+    /// The repaired JSON string
+    pub json: String,
+    /// The encoding the input was decoded with
+    pub encoding: Encoding,
+    /// Every choice made while decoding, empty for plain UTF-8
+    pub decisions: Vec<DecodingDecision>,
+    /// Fixes applied to the decoded text, at byte offsets in the decoded text
+    pub repairs: Vec<Repair>,
 }
 
-impl JsonRepairParser {
-    fn new(input: &str, options: RepairOptions) -> Self {
-        Self {
-            input: input.chars().collect(),
-            pos: 0,
-            output: String::new(),
-            state_stack: vec![ParseState::Root],
-            options,
-        }
-    }
-
-    fn current_char(&self) -> Option<char> {
-        self.input.get(self.pos).copied()
-    }
-
-    fn peek_char(&self, offset: usize) -> Option<char> {
-        self.input.get(self.pos + offset).copied()
-    }
-
-    fn advance(&mut self) -> Option<char> {
-        let ch = self.current_char();
-        if ch.is_some() {
-            self.pos += 1;
-        }
-        ch
-    }
-
-    fn current_state(&self) -> ParseState {
-        self.state_stack.last().copied().unwrap_or(ParseState::Root)
-    }
-
-    fn push_state(&mut self, state: ParseState) {
-        self.state_stack.push(state);
-    }
-
-    fn pop_state(&mut self) -> Option<ParseState> {
-        if self.state_stack.len() > 1 {
-            self.state_stack.pop()
-        } else {
-            None
-        }
-    }
-
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.current_char() {
-            if ch.is_whitespace() {
-                self.advance();
-            } else {
-                break;
-            }
-        }
-    }
-
-    fn skip_comments(&mut self) {
-        if let (Some('/'), Some('/')) = (self.current_char(), self.peek_char(1)) {
-            // Skip line comment
-            while let Some(ch) = self.advance() {
-                if ch == '\n' {
-                    break;
-                }
-            }
-        } else if let (Some('/'), Some('*')) = (self.current_char(), self.peek_char(1)) {
-            // Skip block comment
-            self.advance(); // skip '/'
-            self.advance(); // skip '*'
-            while let Some(ch) = self.advance() {
-                if ch == '*' && self.peek_char(0) == Some('/') {
-                    self.advance(); // skip '/'
-                    break;
-                }
-            }
-        }
-    }
-
-    fn append_char(&mut self, ch: char) {
-        self.output.push(ch);
-    }
-
-    fn append_str(&mut self, s: &str) {
-        self.output.push_str(s);
-    }
-
-    fn parse_string(&mut self) -> Result<(), JsonRepairError> {
-        let quote_char = if self.current_char() == Some('"') {
-            '"'
-        } else if self.current_char() == Some('\'') {
-            '\''
-        } else {
-            // Unquoted string - add quotes
-            self.append_char('"');
-            return self.parse_unquoted_string();
-        };
-
-        self.append_char('"'); // Always use double quotes in output
-        self.advance(); // Skip opening quote
-
-        while let Some(ch) = self.current_char() {
-            if ch == quote_char {
-                self.advance();
-                self.append_char('"');
-                return Ok(());
-            } else if ch == '\\' {
-                self.append_char(ch);
-                self.advance();
-                if let Some(escaped) = self.current_char() {
-                    self.append_char(escaped);
-                    self.advance();
-                }
-            } else if ch == '"' && quote_char == '\'' {
-                // Escape double quotes inside single-quoted strings
-                self.append_str("\\\"");
-                self.advance();
-            } else {
-                if !self.options.ensure_ascii || ch.is_ascii() {
-                    self.append_char(ch);
-                } else {
-                    self.append_str(&format!("\\u{:04x}", ch as u32));
-                }
-                self.advance();
-            }
-        }
-
-        // Unclosed string - close it
-        self.append_char('"');
-        Ok(())
-    }
-
-    fn parse_unquoted_string(&mut self) -> Result<(), JsonRepairError> {
-        while let Some(ch) = self.current_char() {
-            match ch {
-                ',' | '}' | ']' | ':' => break,
-                '"' => {
-                    self.append_str("\\\"");
-                    self.advance();
-                }
-                '\\' => {
-                    self.append_str("\\\\");
-                    self.advance();
-                }
-                _ if ch.is_whitespace() => {
-                    // Check if this is trailing whitespace
-                    let mut temp_pos = self.pos + 1;
-                    let mut found_delimiter = false;
-                    while let Some(temp_ch) = self.input.get(temp_pos) {
-                        if matches!(temp_ch, ',' | '}' | ']' | ':') {
-                            found_delimiter = true;
-                            break;
-                        } else if !temp_ch.is_whitespace() {
-                            break;
-                        }
-                        temp_pos += 1;
-                    }
-
-                    if found_delimiter {
-                        break; // Stop at trailing whitespace
-                    } else {
-                        self.append_char(ch);
-                        self.advance();
-                    }
-                }
-                _ => {
-                    if !self.options.ensure_ascii || ch.is_ascii() {
-                        self.append_char(ch);
-                    } else {
-                        self.append_str(&format!("\\u{:04x}", ch as u32));
-                    }
-                    self.advance();
-                }
-            }
-        }
-        self.append_char('"');
-        Ok(())
-    }
-
-    fn parse_number(&mut self) -> Result<(), JsonRepairError> {
-        let start_pos = self.pos;
-
-        // Handle negative sign
-        if self.current_char() == Some('-') {
-            self.append_char('-');
-            self.advance();
-        }
-
-        // Parse integer part
-        if self.current_char() == Some('0') {
-            self.append_char('0');
-            self.advance();
-        } else {
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() {
-                    self.append_char(ch);
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-        }
-
-        // Parse decimal part
-        if self.current_char() == Some('.') {
-            self.append_char('.');
-            self.advance();
-
-            let before_digits = self.pos;
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() {
-                    self.append_char(ch);
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-
-            // If no digits after decimal, add zero
-            if self.pos == before_digits {
-                self.append_char('0');
-            }
-        }
-
-        // Parse exponent part
-        if matches!(self.current_char(), Some('e') | Some('E')) {
-            self.append_char('e');
-            self.advance();
-
-            if matches!(self.current_char(), Some('+') | Some('-')) {
-                self.append_char(self.current_char().unwrap());
-                self.advance();
-            }
-
-            let before_exp_digits = self.pos;
-            while let Some(ch) = self.current_char() {
-                if ch.is_ascii_digit() {
-                    self.append_char(ch);
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
-
-            // If no digits after exponent, add zero
-            if self.pos == before_exp_digits {
-                self.append_char('0');
-            }
-        }
-
-        // If we didn't parse anything valid, it's not a number
-        if self.pos == start_pos || (self.pos == start_pos + 1 && self.input[start_pos] == '-') {
-            // Reset and treat as unquoted string
-            self.pos = start_pos;
-            self.output
-                .truncate(self.output.len() - (self.pos - start_pos));
-            self.append_char('"');
-            return self.parse_unquoted_string();
-        }
-
-        Ok(())
-    }
-
-    fn parse_literal(&mut self) -> Result<(), JsonRepairError> {
-        let start_pos = self.pos;
-        let mut literal = String::new();
-
-        while let Some(ch) = self.current_char() {
-            if matches!(ch, ',' | '}' | ']' | ':') || ch.is_whitespace() {
-                break;
-            }
-            literal.push(ch);
-            self.advance();
-        }
-
-        match literal.to_lowercase().as_str() {
-            "true" => self.append_str("true"),
-            "false" => self.append_str("false"),
-            "null" | "none" | "undefined" => self.append_str("null"),
-            _ => {
-                // Reset and treat as unquoted string
-                self.pos = start_pos;
-                self.append_char('"');
-                self.parse_unquoted_string()?;
-            }
-        }
-
-        Ok(())
-    }
-
-    fn parse_value(&mut self) -> Result<(), JsonRepairError> {
-        self.skip_whitespace();
-        self.skip_comments();
-        self.skip_whitespace();
-
-        match self.current_char() {
-            None => {
-                // End of input - provide default value based on context
-                match self.current_state() {
-                    ParseState::Array => self.append_str("null"),
-                    _ => self.append_str("null"),
-                }
-            }
-            Some('"') | Some('\'') => {
-                self.parse_string()?;
-            }
-            Some(ch) if ch.is_ascii_digit() || ch == '-' => {
-                self.parse_number()?;
-            }
-            Some('{') => {
-                self.parse_object()?;
-            }
-            Some('[') => {
-                self.parse_array()?;
-            }
-            Some(ch) if ch.is_alphabetic() => {
-                self.parse_literal()?;
-            }
-            Some(_) => {
-                // Invalid character - treat as unquoted string
-                self.append_char('"');
-                self.parse_unquoted_string()?;
-            }
-        }
-
-        Ok(())
-    }
-
-    fn parse_object(&mut self) -> Result<(), JsonRepairError> {
-        self.append_char('{');
-        self.advance(); // Skip '{'
-        self.push_state(ParseState::Object);
-
-        let mut expecting_key = true;
-        let mut needs_comma = false;
-
-        loop {
-            let pos_before = self.pos; // Safety check for infinite loops
-
-            self.skip_whitespace();
-            self.skip_comments();
-            self.skip_whitespace();
-
-            if needs_comma && !expecting_key {
-                if let Some(ch) = self.current_char() {
-                    if ch == '"' || ch == '\'' || ch.is_alphabetic() || ch == '_' {
-                        self.append_char(',');
-                        expecting_key = true;
-                        needs_comma = false;
-                    }
-                }
-            }
-
-            match self.current_char() {
-                None => {
-                    // Incomplete object - close it
-                    self.append_char('}');
-                    break;
-                }
-                Some('}') => {
-                    self.advance();
-                    self.append_char('}');
-                    break;
-                }
-                Some(',') => {
-                    self.advance();
-                    // Skip trailing or multiple commas
-                    self.skip_whitespace();
-                    if matches!(self.current_char(), Some('}') | None) {
-                        // Trailing comma - ignore it
-                        continue;
-                    }
-                    if !expecting_key {
-                        self.append_char(',');
-                        expecting_key = true;
-                        needs_comma = false;
-                    }
-                    continue;
-                }
-                _ => {
-                    if needs_comma {
-                        self.append_char(',');
-                    }
-
-                    if expecting_key {
-                        // Parse key
-                        if matches!(self.current_char(), Some('"') | Some('\'')) {
-                            self.parse_string()?;
-                        } else {
-                            // Unquoted key
-                            self.append_char('"');
-                            self.parse_unquoted_string()?;
-                        }
-
-                        // Expect colon
-                        self.skip_whitespace();
-                        if self.current_char() == Some(':') {
-                            self.advance();
-                            self.append_char(':');
-                        } else {
-                            self.append_char(':');
-                        }
-
-                        // Parse value
-                        self.parse_value()?;
-
-                        expecting_key = false;
-                        needs_comma = true;
-                    } else {
-                        // We have a value but expected a key - this shouldn't happen
-                        // Add a default key
-                        self.append_str("\"unknown\":");
-                        self.parse_value()?;
-                        expecting_key = false;
-                        needs_comma = true;
-                    }
-                }
-            }
-
-            // Safety check: ensure we're making progress
-            if self.pos == pos_before && self.pos < self.input.len() {
-                // We're stuck - advance one character to avoid infinite loop
-                self.advance();
-            }
-        }
-
-        self.pop_state();
-        Ok(())
-    }
-
-    fn parse_array(&mut self) -> Result<(), JsonRepairError> {
-        self.append_char('[');
-        self.advance(); // Skip '['
-        self.push_state(ParseState::Array);
-
-        let mut needs_comma = false;
-
-        loop {
-            self.skip_whitespace();
-            self.skip_comments();
-            self.skip_whitespace();
-
-            match self.current_char() {
-                None => {
-                    // Incomplete array - close it
-                    self.append_char(']');
-                    break;
-                }
-                Some(']') => {
-                    self.advance();
-                    self.append_char(']');
-                    break;
-                }
-                Some(',') => {
-                    self.advance();
-                    // Skip trailing or multiple commas
-                    self.skip_whitespace();
-                    if matches!(self.current_char(), Some(']') | None) {
-                        // Trailing comma - ignore it
-                        continue;
-                    }
-                    if needs_comma {
-                        self.append_char(',');
-                        needs_comma = false;
-                    }
-                    continue;
-                }
-                _ => {
-                    if needs_comma {
-                        self.append_char(',');
-                    }
-
-                    self.parse_value()?;
-                    needs_comma = true;
-                }
-            }
-        }
-
-        self.pop_state();
-        Ok(())
-    }
-
-    fn parse(&mut self) -> Result<(), JsonRepairError> {
-        // Skip any leading non-JSON content and handle markdown code blocks
-        let input_str: String = self.input.iter().collect();
-
-        // Handle markdown code blocks
-        if let Some(start) = input_str.find("```json") {
-            if let Some(end) = input_str.rfind("```") {
-                if end > start + 7 {
-                    let json_content = &input_str[start + 7..end];
-                    self.input = json_content.chars().collect();
-                    self.pos = 0;
-                }
-            }
-        }
-
-        self.skip_whitespace();
-
-        // Look for JSON start markers, skipping explanatory text
-        while let Some(ch) = self.current_char() {
-            if matches!(ch, '{' | '[' | '"' | '\'' | '-')
-                || ch.is_ascii_digit()
-                || ch.is_alphabetic()
-            {
-                break;
-            }
-            self.advance();
-        }
-
-        // If we find text like "Here's the JSON:", skip to the actual JSON
-        let remaining: String = self.input[self.pos..].iter().collect();
-        if let Some(json_start) = remaining.find('{').or_else(|| remaining.find('[')) {
-            self.pos += json_start;
-        }
-
-        self.parse_value()?;
-
-        // Skip any trailing content
-        self.skip_whitespace();
-
-        Ok(())
-    }
-
-    fn get_result(self) -> String {
-        self.output
+impl RepairedBytes {
+    /// Confidence in the repair of the decoded text, from 0 to 1, see [`confidence`]
+    pub fn confidence(&self) -> f64 {
+        confidence(&self.repairs)
     }
 }
 
@@ -639,31 +283,235 @@ impl JsonRepairParser {
 /// }
 /// ```
 pub fn repair_json(json_str: &str, options: &RepairOptions) -> Result<String, JsonRepairError> {
+    repair_json_with_report(json_str, options).map(|report| report.json)
+}
+
+/// Repair a broken JSON string and report every fix that was needed
+///
+/// # Arguments
+///
+/// * `json_str` - The broken JSON string to repair
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(RepairReport)` - The repaired JSON string and the fixes applied to the input
+/// * `Err(JsonRepairError)` - If the JSON is too broken to repair
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{repair_json_with_report, Repair, RepairKind, RepairOptions};
+///
+/// let report = repair_json_with_report(r#"{"tags": ["a", "b",]"#, &RepairOptions::default()).unwrap();
+/// assert_eq!(report.json, r#"{"tags":["a","b"]}"#);
+/// assert_eq!(
+///     report.repairs,
+///     vec![
+///         Repair { kind: RepairKind::RemovedComma, offset: 18, path: "/tags/1".to_string() },
+///         Repair { kind: RepairKind::ClosedObject, offset: 20, path: String::new() },
+///     ]
+/// );
+/// // Closing a container is a safe guess, inventing a key is not
+/// assert!(report.confidence() > 0.9);
+/// ```
+pub fn repair_json_with_report(
+    json_str: &str,
+    options: &RepairOptions,
+) -> Result<RepairReport, JsonRepairError> {
     if json_str.trim().is_empty() {
-        return Ok("{}".to_string());
+        let end = json_str.len();
+        return Ok(RepairReport {
+            json: "{}".to_string(),
+            repairs: vec![Repair {
+                kind: RepairKind::InsertedValue,
+                offset: end,
+                path: String::new(),
+            }],
+            source_map: options.source_map.then(|| {
+                SourceMap::new(vec![Span {
+                    pointer: String::new(),
+                    input: end..end,
+                    output: 0..2,
+                }])
+            }),
+        });
     }
 
-    // First try to parse as-is if skip_json_loads is false
-    if !options.skip_json_loads {
-        if let Ok(value) = serde_json::from_str::<Value>(json_str) {
-            // Always return consistent compact format
-            return Ok(serde_json::to_string(&value)?);
-        }
+    // First try to parse as-is if skip_json_loads is false; the source map needs the
+    // parser even for valid JSON, and index maps need it for where they start
+    let index_maps = options.index_maps && !options.skip_json_loads;
+    if !options.skip_json_loads
+        && !options.source_map
+        && !index_maps
+        && let Ok(value) = serde_json::from_str::<Value>(json_str)
+    {
+        // Always return consistent compact format
+        return Ok(RepairReport {
+            json: serde_json::to_string(&value)?,
+            repairs: Vec::new(),
+            source_map: None,
+        });
     }
 
-    let mut parser = JsonRepairParser::new(json_str, options.clone());
+    let parser_options = RepairOptions {
+        source_map: options.source_map || index_maps,
+        ..options.clone()
+    };
+    let mut parser = JsonRepairParser::new(json_str, parser_options);
     parser.parse()?;
 
+    let mut repairs = parser.take_repairs();
+    let source_map = parser.take_spans().map(SourceMap::new);
     let repaired = parser.get_result();
 
     // Validate the repaired JSON unless skipping validation
     if !options.skip_json_loads {
-        let parsed: Value = serde_json::from_str(&repaired)?;
+        let mut parsed: Value =
+            serde_json::from_str(&repaired).map_err(|error| {
+                match source_map
+                    .as_ref()
+                    .and_then(|map| map.locate(&repaired, &error))
+                {
+                    Some(span) => JsonRepairError::SerdeErrorAt { error, span },
+                    None => JsonRepairError::SerdeError(error),
+                }
+            })?;
+        if index_maps && let Some(map) = &source_map {
+            convert_index_maps(&mut parsed, &mut String::new(), map, &mut repairs);
+        }
         // Return compact JSON format consistently
-        return Ok(serde_json::to_string(&parsed)?);
+        return Ok(RepairReport {
+            json: serde_json::to_string(&parsed)?,
+            repairs,
+            source_map: source_map.filter(|_| options.source_map),
+        });
     }
 
-    Ok(repaired)
+    Ok(RepairReport {
+        json: repaired,
+        repairs,
+        source_map,
+    })
+}
+
+/// Turn the objects in `value` keyed `"0"` to `"n-1"` into arrays, innermost first,
+/// reporting each at the position `map` gives for it
+fn convert_index_maps(
+    value: &mut Value,
+    pointer: &mut String,
+    map: &SourceMap,
+    repairs: &mut Vec<Repair>,
+) {
+    let len = pointer.len();
+    match value {
+        Value::Object(members) => {
+            for (key, member) in members.iter_mut() {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                convert_index_maps(member, pointer, map, repairs);
+                pointer.truncate(len);
+            }
+            if members.is_empty()
+                || !(0..members.len()).all(|index| members.contains_key(&index.to_string()))
+            {
+                return;
+            }
+            let elements = (0..members.len())
+                .filter_map(|index| members.remove(&index.to_string()))
+                .collect();
+            *value = Value::Array(elements);
+            repairs.push(Repair {
+                kind: RepairKind::ConvertedToArray,
+                offset: map.get(pointer).map_or(0, |span| span.start),
+                path: pointer.clone(),
+            });
+        }
+        Value::Array(elements) => {
+            for (index, element) in elements.iter_mut().enumerate() {
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                convert_index_maps(element, pointer, map, repairs);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Repair a broken JSON string with as few changes as possible
+///
+/// Only the characters that need fixing change; whitespace, indentation, key order,
+/// number spelling and quoting are kept everywhere else, so that repairing a file makes
+/// a small diff. Characters are kept as they are, whatever `ensure_ascii`.
+///
+/// # Arguments
+///
+/// * `json_str` - The broken JSON string to repair
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(String)` - The input with the fixes applied
+/// * `Err(JsonRepairError)` - If the JSON is too broken to repair
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{repair_json_minimal, RepairOptions};
+///
+/// let broken = "{\n  \"b\": 1.50,\n  \"a\": [1, 2,],\n}\n";
+/// let repaired = repair_json_minimal(broken, &RepairOptions::default()).unwrap();
+/// assert_eq!(repaired, "{\n  \"b\": 1.50,\n  \"a\": [1, 2]\n}\n");
+/// ```
+pub fn repair_json_minimal(
+    json_str: &str,
+    options: &RepairOptions,
+) -> Result<String, JsonRepairError> {
+    Ok(apply_edits(
+        json_str,
+        &repair_json_edits(json_str, options)?,
+    ))
+}
+
+/// Repair broken JSON from bytes of unknown encoding
+///
+/// A UTF-8 byte order mark is stripped and UTF-16 (LE or BE) is detected by its byte
+/// order mark. Input that is not UTF-8 and holds no multi-byte UTF-8 character is read
+/// as Windows-1252, and any remaining invalid sequence is replaced with U+FFFD, so
+/// decoding itself never fails.
+///
+/// # Arguments
+///
+/// * `bytes` - The broken JSON bytes to decode and repair
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(RepairedBytes)` - The repaired JSON string and the decoding decisions
+/// * `Err(JsonRepairError)` - If the JSON is too broken to repair
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{repair_bytes, DecodingDecision, Encoding, RepairOptions};
+///
+/// let repaired = repair_bytes(b"\xEF\xBB\xBF{name: 'Jos\xC3\xA9'", &RepairOptions::default()).unwrap();
+/// assert_eq!(repaired.json, r#"{"name":"José"}"#);
+/// assert_eq!(repaired.decisions, vec![DecodingDecision::BomStripped(Encoding::Utf8)]);
+/// ```
+pub fn repair_bytes(
+    bytes: &[u8],
+    options: &RepairOptions,
+) -> Result<RepairedBytes, JsonRepairError> {
+    let decoded = encoding::decode(bytes);
+    let report = repair_json_with_report(&decoded.text, options)?;
+    Ok(RepairedBytes {
+        json: report.json,
+        encoding: decoded.encoding,
+        decisions: decoded.decisions,
+        repairs: report.repairs,
+    })
 }
 
 /// Repair and parse a JSON string, returning the parsed Value
@@ -707,13 +555,14 @@ pub fn loads(json_str: &str, options: &RepairOptions) -> Result<Value, JsonRepai
 /// # Returns
 ///
 /// * `Ok(Value)` - The parsed JSON value
-/// * `Err(JsonRepairError)` - If the file cannot be read or JSON cannot be repaired
+/// * `Err(JsonRepairError)` - If the file cannot be read, is not valid UTF-8 or JSON
+///   cannot be repaired
 pub fn from_file<P: AsRef<Path>>(
     path: P,
     options: &RepairOptions,
 ) -> Result<Value, JsonRepairError> {
-    let content = fs::read_to_string(path)?;
-    loads(&content, options)
+    let content = fs::read(path)?;
+    loads(std::str::from_utf8(&content)?, options)
 }
 
 /// Repair and parse JSON from a reader
@@ -726,11 +575,56 @@ pub fn from_file<P: AsRef<Path>>(
 /// # Returns
 ///
 /// * `Ok(Value)` - The parsed JSON value
-/// * `Err(JsonRepairError)` - If the reader cannot be read or JSON cannot be repaired
+/// * `Err(JsonRepairError)` - If the reader cannot be read, is not valid UTF-8 or JSON
+///   cannot be repaired
+///
+/// Use [`repair_bytes`] for input that may not be UTF-8.
 pub fn load<R: Read>(mut reader: R, options: &RepairOptions) -> Result<Value, JsonRepairError> {
-    let mut content = String::new();
-    reader.read_to_string(&mut content)?;
-    loads(&content, options)
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+    loads(std::str::from_utf8(&content)?, options)
+}
+
+/// Repair JSON from a reader and stream the repaired JSON to a writer
+///
+/// The input is never held in memory as a whole: it is read through a bounded window
+/// and the output is written as it is produced, so memory is bounded by the nesting
+/// depth of the document rather than its size.
+///
+/// Since the output is never held as a whole either, it is not re-validated with
+/// serde_json: it is written compactly with keys in their original order, as with
+/// `skip_json_loads`. A markdown code block that is left unfinished is not cut at its
+/// closing fence, as the end of a stream is not known in advance. For the same reason,
+/// several top-level values are only repaired with [`RootValues::First`].
+///
+/// # Arguments
+///
+/// * `reader` - A reader containing JSON data
+/// * `writer` - Where the repaired JSON is written
+/// * `options` - Configuration options for the repair process
+///
+/// # Returns
+///
+/// * `Ok(())` - Once the repaired JSON has been written and the writer flushed
+/// * `Err(JsonRepairError)` - If reading, decoding or writing fails
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{repair_to_writer, RepairOptions};
+///
+/// let mut output = Vec::new();
+/// repair_to_writer(&b"{name: 'John', age: 30,"[..], &mut output, &RepairOptions::default())
+///     .unwrap();
+/// assert_eq!(output, br#"{"name":"John","age":30}"#);
+/// ```
+pub fn repair_to_writer<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    options: &RepairOptions,
+) -> Result<(), JsonRepairError> {
+    let mut parser = JsonRepairParser::from_reader(reader, writer, options.clone());
+    parser.parse()
 }
 
 #[cfg(test)]
@@ -808,6 +702,10 @@ mod tests {
         // Array with single trailing comma only
         let result = repair_json(r#"[1, 2, 3]"#, &options).unwrap();
         assert_eq!(result, r#"[1,2,3]"#);
+
+        // Incomplete array of objects
+        let result = repair_json(r#"[{"a": 1}, {"b": 2"#, &options).unwrap();
+        assert_eq!(result, r#"[{"a":1},{"b":2}]"#);
     }
 
     #[test]
@@ -827,6 +725,63 @@ mod tests {
         // Invalid numbers should become strings - test with simpler case
         let result = repair_json(r#"{invalid: abc123}"#, &options).unwrap();
         assert_eq!(result, r#"{"invalid":"abc123"}"#);
+
+        // A minus sign alone is text
+        let result = repair_json(r#"{"range": -, "dash": - n/a}"#, &options).unwrap();
+        assert_eq!(result, r#"{"dash":"- n/a","range":"-"}"#);
+
+        // Numbers as people write them
+        let broken = "{\"id\": 007, \"n\": +5, \"f\": .5, \"pop\": 1,000,000, \"pi\": 3,14, \
+                      \"rate\": 30%, \"price\": $12.50, \"views\": 1.2k, \"age\": 42 years}";
+        let result = repair_json(broken, &options).unwrap();
+        assert_eq!(
+            result,
+            r#"{"age":"42 years","f":0.5,"id":7,"n":5,"pi":3.14,"pop":1000000,"price":"$12.50","rate":"30%","views":"1.2k"}"#
+        );
+        let result = repair_json("[1,234, 1,000, 1,000,000, 007]", &options).unwrap();
+        assert_eq!(result, "[1,234,1000,1000000,7]");
+        let result = repair_json("[12345,000, 1,00]", &options).unwrap();
+        assert_eq!(result, r#"[12345,"000",1,"00"]"#);
+        // Notations that are not read stay text rather than splitting into keys
+        let result = repair_json("{\"a\": 0x10, \"b\": 1_000, \"c\": [0x1F]}", &options).unwrap();
+        assert_eq!(result, r#"{"a":"0x10","b":"1_000","c":["0x1F"]}"#);
+
+        let numeric = RepairOptions {
+            numbers: NumberOptions {
+                percent: NumberRepair::Number,
+                currency: NumberRepair::Number,
+                magnitude_suffix: NumberRepair::Number,
+                unit: NumberRepair::Number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let report = repair_json_with_report(broken, &numeric).unwrap();
+        assert_eq!(
+            report.json,
+            r#"{"age":42,"f":0.5,"id":7,"n":5,"pi":3.14,"pop":1000000,"price":12.5,"rate":0.3,"views":1200}"#
+        );
+        assert_eq!(
+            report
+                .repairs
+                .iter()
+                .filter(|repair| repair.kind == RepairKind::DroppedUnit)
+                .map(|repair| repair.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/price", "/age"]
+        );
+
+        let strict = RepairOptions {
+            numbers: NumberOptions {
+                thousands_separators: NumberRepair::Error,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            repair_json("{\"a\": 1,000}", &strict),
+            Err(JsonRepairError::InvalidNumber { text, offset: 6 }) if text == "1,000"
+        ));
     }
 
     #[test]
@@ -845,11 +800,18 @@ mod tests {
 
     #[test]
     fn test_unicode_preservation() {
-        let mut options = RepairOptions::default();
-        options.ensure_ascii = false;
+        let options = RepairOptions {
+            ensure_ascii: false,
+            ..Default::default()
+        };
 
         let result = repair_json(r#"{"chinese": "统一码"}"#, &options).unwrap();
         assert!(result.contains("统一码"));
+
+        // Characters outside the BMP survive `ensure_ascii`
+        let options = RepairOptions::default();
+        let result = repair_json("{emoji: '😀'}", &options).unwrap();
+        assert_eq!(result, "{\"emoji\":\"😀\"}");
     }
 
     #[test]
@@ -922,6 +884,44 @@ mod tests {
         assert_eq!(result, r#"{"age":30,"name":"John"}"#);
     }
 
+    #[test]
+    fn test_reasoning_blocks() {
+        let options = RepairOptions {
+            skip_reasoning: true,
+            ..Default::default()
+        };
+
+        // Draft JSON inside the reasoning is ignored
+        let result = repair_json(
+            r#"<think>Maybe {"name": "Draft"}? No, fix the age.</think>
+        {"name": "John", "age": 30}"#,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(result, r#"{"age":30,"name":"John"}"#);
+
+        // Opening tag was part of the prompt
+        let result = repair_json(r#"I think {"a": 1} works</think>{"a": 2}"#, &options).unwrap();
+        assert_eq!(result, r#"{"a":2}"#);
+
+        // Custom tag names, matched case-insensitively
+        let custom = RepairOptions {
+            reasoning_tags: vec!["scratchpad".to_string()],
+            ..options.clone()
+        };
+        let result = repair_json(r#"<Scratchpad>[1, 2]</SCRATCHPAD>[3, 4"#, &custom).unwrap();
+        assert_eq!(result, "[3,4]");
+
+        // Unclosed block at the start contains no answer
+        let result = repair_json(r#"<think>I will answer {"a": 1"#, &options).unwrap();
+        assert_eq!(result, "{}");
+
+        // Disabled by default
+        let result =
+            repair_json(r#"<think>{"a": 1}</think>{"a": 2}"#, &Default::default()).unwrap();
+        assert_eq!(result, r#"{"a":1}"#);
+    }
+
     #[test]
     fn test_string_escaping() {
         let options = RepairOptions::default();
@@ -933,6 +933,10 @@ mod tests {
         // Unescaped quotes in single-quoted strings
         let result = repair_json(r#"{'message': 'He said "Hello"'}"#, &options).unwrap();
         assert_eq!(result, r#"{"message":"He said \"Hello\""}"#);
+
+        // Raw control characters
+        let result = repair_json("{\"message\": \"line 1\nline 2\"}", &options).unwrap();
+        assert_eq!(result, r#"{"message":"line 1\nline 2"}"#);
     }
 
     #[test]
@@ -954,10 +958,577 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_repair_report() {
+        let options = RepairOptions::default();
+        let kinds = |broken: &str| -> Vec<RepairKind> {
+            repair_json_with_report(broken, &options)
+                .unwrap()
+                .repairs
+                .into_iter()
+                .map(|repair| repair.kind)
+                .collect()
+        };
+
+        // Valid JSON needs no fixes, even when the parser runs on it
+        let valid = r#"{"a": [1, -2.5E+3, "x\ny \u00e9"], "b": null} "#;
+        assert!(kinds(valid).is_empty());
+        let skip = RepairOptions {
+            skip_json_loads: true,
+            ..Default::default()
+        };
+        assert!(repair_json_with_report(valid, &skip).unwrap().is_valid());
+
+        assert_eq!(
+            kinds("Sure! {name: 'John' // the name\n age: None,}"),
+            vec![
+                RepairKind::SkippedText,
+                RepairKind::QuotedKey,
+                RepairKind::ReplacedQuotes,
+                RepairKind::RemovedComment,
+                RepairKind::InsertedComma,
+                RepairKind::QuotedKey,
+                RepairKind::NormalizedLiteral,
+                RepairKind::RemovedComma,
+            ]
+        );
+        assert_eq!(
+            kinds("```json\n[1 2, 'a\tb\n```"),
+            vec![
+                RepairKind::StrippedCodeFence,
+                RepairKind::InsertedComma,
+                RepairKind::ReplacedQuotes,
+                RepairKind::EscapedCharacter,
+                RepairKind::EscapedCharacter,
+                RepairKind::ClosedString,
+                RepairKind::ClosedArray,
+            ]
+        );
+        assert_eq!(
+            kinds(r#"{"a": 1. "b"}"#),
+            vec![
+                RepairKind::NormalizedNumber,
+                RepairKind::InsertedComma,
+                RepairKind::InsertedColon,
+                RepairKind::InsertedValue,
+            ]
+        );
+        assert_eq!(kinds(" "), vec![RepairKind::InsertedValue]);
+
+        // Offsets point into the input
+        let report = repair_json_with_report("[1, 2] trailing", &options).unwrap();
+        assert_eq!(report.json, "[1,2]");
+        assert_eq!(
+            report.repairs,
+            vec![Repair {
+                kind: RepairKind::SkippedText,
+                offset: 7,
+                path: String::new(),
+            }]
+        );
+
+        let broken = "{\n  \"name\": \"Zoë\",\n  \"age\": 30,\n}";
+        let report = repair_json_with_report(broken, &options).unwrap();
+        assert_eq!(report.repairs[0].kind, RepairKind::RemovedComma);
+        assert_eq!(report.repairs[0].line_col(broken), (3, 12));
+        let comma = broken.find("Zoë\",").unwrap() + "Zoë\"".len();
+        let repair = Repair {
+            kind: RepairKind::InsertedComma,
+            offset: comma,
+            path: "/name".to_string(),
+        };
+        assert_eq!(repair.line_col(broken), (2, 16));
+
+        // Paths point to the value each fix belongs to
+        let report = repair_json_with_report("{users: [{'name': 'a'}, {name: 'b' 1", &options);
+        let paths: Vec<(RepairKind, String)> = report
+            .unwrap()
+            .repairs
+            .into_iter()
+            .map(|repair| (repair.kind, repair.path))
+            .collect();
+        let path = |kind, path: &str| (kind, path.to_string());
+        assert_eq!(
+            paths,
+            vec![
+                path(RepairKind::QuotedKey, "/users"),
+                path(RepairKind::ReplacedQuotes, "/users/0/name"),
+                path(RepairKind::ReplacedQuotes, "/users/0/name"),
+                path(RepairKind::QuotedKey, "/users/1/name"),
+                path(RepairKind::ReplacedQuotes, "/users/1/name"),
+                path(RepairKind::InsertedComma, "/users/1/name"),
+                path(RepairKind::InventedKey, "/users/1/unknown"),
+                path(RepairKind::ClosedObject, "/users/1"),
+                path(RepairKind::ClosedArray, "/users"),
+                path(RepairKind::ClosedObject, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_headless_containers() {
+        let options = RepairOptions::default();
+        let repair = |input: &str| repair_json(input, &options).unwrap();
+
+        assert_eq!(
+            repair("\"name\": \"John\", \"age\": 30}"),
+            r#"{"age":30,"name":"John"}"#
+        );
+        assert_eq!(
+            repair("\"user\": {\"name\": \"x\"}, \"tags\": [\"a}\"]}"),
+            r#"{"tags":["a}"],"user":{"name":"x"}}"#
+        );
+        assert_eq!(
+            repair("name: 'John', age: 30}"),
+            r#"{"age":30,"name":"John"}"#
+        );
+        assert_eq!(repair("1, 2, 3]"), "[1,2,3]");
+        assert_eq!(repair("\"a\", \"b\"]"), r#"["a","b"]"#);
+        assert_eq!(repair("```json\n\"a\": 1}\n```"), r#"{"a":1}"#);
+        // Cut off before any closing delimiter, the quoted key is enough
+        assert_eq!(repair("\"name\": \"Jo"), r#"{"name":"Jo"}"#);
+
+        let report = repair_json_with_report("1, 2]", &options).unwrap();
+        assert_eq!(report.repairs[0].kind, RepairKind::OpenedArray);
+        assert_eq!(report.repairs[0].offset, 0);
+
+        // A sentence that starts like a key, with balanced brackets, is prose
+        assert_eq!(repair("Note: the result is {\"a\": 1}"), r#"{"a":1}"#);
+        assert_eq!(repair("\"hello\""), r#""hello""#);
+    }
+
+    #[test]
+    fn test_container_kinds() {
+        let options = RepairOptions::default();
+        let repair = |input: &str| repair_json(input, &options).unwrap();
+
+        assert_eq!(
+            repair("[\"name\": \"John\", \"age\": 30]"),
+            r#"{"age":30,"name":"John"}"#
+        );
+        assert_eq!(
+            repair("{\"red\", \"green\", \"blue\"}"),
+            r#"["red","green","blue"]"#
+        );
+        assert_eq!(repair("{1, 2, 3}"), "[1,2,3]");
+        assert_eq!(
+            repair_json_minimal("[\"a\": 1]\n", &options).unwrap(),
+            "{\"a\": 1}\n"
+        );
+        // Each container closes with the delimiter it was opened with
+        assert_eq!(
+            repair("[[\"a\": 1], {\"b\", {\"c\": 2}}]"),
+            r#"[{"a":1},["b",{"c":2}]]"#
+        );
+        // Bare values among pairs, or prose in brackets, keep their kind
+        assert_eq!(repair("{\"a\", \"b\": 1}"), r#"{"a":"","b":1}"#);
+        assert_eq!(repair("[\"a: b\", \"c\"]"), r#"["a: b","c"]"#);
+
+        let report = repair_json_with_report("{\"x\": {\"a\", \"b\"}}", &options).unwrap();
+        assert_eq!(report.json, r#"{"x":["a","b"]}"#);
+        assert_eq!(
+            report.repairs,
+            vec![Repair {
+                kind: RepairKind::ConvertedToArray,
+                offset: 6,
+                path: "/x".to_string(),
+            }]
+        );
+
+        // Index maps only on request
+        let index_maps = RepairOptions {
+            index_maps: true,
+            ..Default::default()
+        };
+        let input = "{\"steps\": {\"1\": \"b\", \"0\": \"a\"}, \"ids\": {\"0\": 1, \"2\": 3}}";
+        assert_eq!(
+            repair(input),
+            r#"{"ids":{"0":1,"2":3},"steps":{"0":"a","1":"b"}}"#
+        );
+        let report = repair_json_with_report(input, &index_maps).unwrap();
+        assert_eq!(report.json, r#"{"ids":{"0":1,"2":3},"steps":["a","b"]}"#);
+        assert_eq!(
+            report.repairs,
+            vec![Repair {
+                kind: RepairKind::ConvertedToArray,
+                offset: 10,
+                path: "/steps".to_string(),
+            }]
+        );
+        assert!(report.source_map.is_none());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let with = |placeholders| RepairOptions {
+            placeholders,
+            ..Default::default()
+        };
+        let repair = |input: &str, placeholders| repair_json(input, &with(placeholders));
+
+        // Kept as strings by default
+        assert_eq!(
+            repair("[1, 2, ...]", Placeholders::Keep).unwrap(),
+            r#"[1,2,"..."]"#
+        );
+
+        assert_eq!(
+            repair("[1, 2, 3, ...]", Placeholders::Drop).unwrap(),
+            "[1,2,3]"
+        );
+        assert_eq!(repair("[1, …, 4]", Placeholders::Drop).unwrap(), "[1,4]");
+        assert_eq!(repair("[..., 1]", Placeholders::Drop).unwrap(), "[1]");
+        assert_eq!(
+            repair("{\"a\": 1, ..., \"b\": 2}", Placeholders::Drop).unwrap(),
+            r#"{"a":1,"b":2}"#
+        );
+        assert_eq!(
+            repair("{\"a\": ..., \"b\": [ /* more */ ]}", Placeholders::Drop).unwrap(),
+            r#"{"a":null,"b":[]}"#
+        );
+        // Dots inside strings or numbers are data
+        assert_eq!(
+            repair("[\"...\", \"a...b\", 1.5]", Placeholders::Drop).unwrap(),
+            r#"["...","a...b",1.5]"#
+        );
+
+        assert_eq!(
+            repair("[1, 2, ...]", Placeholders::Null).unwrap(),
+            "[1,2,null]"
+        );
+        assert_eq!(
+            repair("{\"a\": 1, ...}", Placeholders::Null).unwrap(),
+            r#"{"a":1}"#
+        );
+
+        assert!(matches!(
+            repair("{\"a\": [1, ...]}", Placeholders::Error),
+            Err(JsonRepairError::ElidedContent { offset: 10 })
+        ));
+        assert!(matches!(
+            repair("{\"a\": 1, …}", Placeholders::Error),
+            Err(JsonRepairError::ElidedContent { offset: 9 })
+        ));
+
+        // A comment alone in a container or value slot stands for what was left out
+        assert!(matches!(
+            repair("{\"items\": [ /* more */ ]}", Placeholders::Error),
+            Err(JsonRepairError::ElidedContent { offset: 12 })
+        ));
+        assert!(matches!(
+            repair("{\"a\": // todo\n}", Placeholders::Error),
+            Err(JsonRepairError::ElidedContent { offset: 6 })
+        ));
+        assert_eq!(
+            repair(
+                "{\"a\": [ /* more */ ], \"b\": // todo\n}",
+                Placeholders::Null
+            )
+            .unwrap(),
+            r#"{"a":[null],"b":null}"#
+        );
+        // Comments next to data are only comments
+        assert_eq!(
+            repair(
+                "{// note\n\"a\": [1 /* one */], \"b\": 2 // two\n}",
+                Placeholders::Error
+            )
+            .unwrap(),
+            r#"{"a":[1],"b":2}"#
+        );
+
+        let report = repair_json_with_report("[1, ...]", &with(Placeholders::Drop)).unwrap();
+        assert_eq!(
+            report.repairs.last(),
+            Some(&Repair {
+                kind: RepairKind::DroppedPlaceholder,
+                offset: 4,
+                path: "/0".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_mismatched_closers() {
+        let options = RepairOptions::default();
+        let repair = |input: &str| repair_json(input, &options).unwrap();
+
+        // A closer for an outer container closes the inner ones first
+        assert_eq!(repair("{\"a\": [1, 2}"), r#"{"a":[1,2]}"#);
+        assert_eq!(repair("{\"a\": [[1, 2}"), r#"{"a":[[1,2]]}"#);
+        assert_eq!(repair("[{\"a\": 1]"), r#"[{"a":1}]"#);
+        assert_eq!(repair("[{\"a\": {\"b\": 1]"), r#"[{"a":{"b":1}}]"#);
+
+        // Closers that close nothing open are dropped
+        assert_eq!(repair("{\"a\": 1]}"), r#"{"a":1}"#);
+        assert_eq!(repair("{\"a\": [1, 2]]}"), r#"{"a":[1,2]}"#);
+        assert_eq!(repair("[1, 2}, 3]"), "[1,2,3]");
+        assert_eq!(repair("[1: 2]"), "[1,2]");
+
+        let report = repair_json_with_report("{\"a\": 1, ]}", &options).unwrap();
+        assert_eq!(report.json, r#"{"a":1}"#);
+        assert!(
+            report
+                .repairs
+                .iter()
+                .any(|repair| repair.kind == RepairKind::RemovedCloser)
+        );
+    }
+
+    #[test]
+    fn test_root_values() {
+        let with = |root_values| RepairOptions {
+            root_values,
+            ..Default::default()
+        };
+        let input = "{\"a\": 1}\n{\"a\": 2}, [3] Hope this helps!";
+
+        let report = repair_json_with_report(input, &with(RootValues::Wrap)).unwrap();
+        assert_eq!(report.json, r#"[{"a":1},{"a":2},[3]]"#);
+        let wrapped = report
+            .repairs
+            .iter()
+            .filter(|repair| repair.kind == RepairKind::WrappedValue)
+            .map(|repair| repair.offset);
+        assert_eq!(wrapped.collect::<Vec<_>>(), vec![0, 9, 19]);
+        assert!(!report.is_valid());
+        let options = RepairOptions {
+            source_map: true,
+            ..with(RootValues::Wrap)
+        };
+        let report = repair_json_with_report(input, &options).unwrap();
+        assert_eq!(report.source_map.unwrap().get("/1/a"), Some(15..16));
+
+        let kinds = |report: RepairReport| -> Vec<(RepairKind, usize)> {
+            report
+                .repairs
+                .iter()
+                .map(|repair| (repair.kind, repair.offset))
+                .collect()
+        };
+        let first = repair_json_with_report(input, &RepairOptions::default()).unwrap();
+        assert_eq!(first.json, r#"{"a":1}"#);
+        assert_eq!(kinds(first), vec![(RepairKind::DroppedValue, 9)]);
+        let last = repair_json_with_report(input, &with(RootValues::Last)).unwrap();
+        assert_eq!(last.json, "[3]");
+        assert_eq!(
+            kinds(last),
+            vec![
+                (RepairKind::DroppedValue, 0),
+                (RepairKind::DroppedValue, 9),
+                (RepairKind::SkippedText, 23),
+            ]
+        );
+        assert!(matches!(
+            repair_json(input, &with(RootValues::Error)),
+            Err(JsonRepairError::MultipleValues { offset: 9 })
+        ));
+
+        // Tool calls in separate code fences
+        let fenced = "```json\n{\"tool\": \"a\"}\n```\n\n```json\n{\"tool\": \"b\"}\n```";
+        assert_eq!(
+            repair_json(fenced, &with(RootValues::Wrap)).unwrap(),
+            r#"[{"tool":"a"},{"tool":"b"}]"#
+        );
+        // Prose in brackets after the JSON is not another value
+        let answer = "Answer: {\"a\": 1} [see note]";
+        assert_eq!(
+            repair_json(answer, &with(RootValues::Wrap)).unwrap(),
+            r#"{"a":1}"#
+        );
+        assert_eq!(
+            kinds(repair_json_with_report(answer, &RepairOptions::default()).unwrap()),
+            vec![(RepairKind::SkippedText, 0), (RepairKind::SkippedText, 17)]
+        );
+        assert_eq!(
+            repair_json_minimal("{\"a\": 1}\n{\"a\": 2}\n", &with(RootValues::Last)).unwrap(),
+            "{\"a\": 2}\n"
+        );
+
+        // Streamed output cannot be wrapped after the fact
+        let mut output = Vec::new();
+        let result = repair_to_writer("[1] [2]".as_bytes(), &mut output, &with(RootValues::Wrap));
+        assert!(matches!(
+            result,
+            Err(JsonRepairError::MultipleValues { offset: 4 })
+        ));
+        let mut output = Vec::new();
+        repair_to_writer("[1] [2]".as_bytes(), &mut output, &RepairOptions::default()).unwrap();
+        assert_eq!(output, b"[1]");
+    }
+
+    #[test]
+    fn test_repair_json_minimal() {
+        let options = RepairOptions::default();
+        let minimal = |broken: &str| repair_json_minimal(broken, &options).unwrap();
+
+        // Valid JSON is left exactly as it is
+        let valid = "{\n    \"z\": 1.0E+2,\n    \"a\": [ \"é\", null ]\n}\n";
+        assert_eq!(minimal(valid), valid);
+
+        assert_eq!(
+            minimal("{\n  // note\n  name: 'John',\n  \"tags\": [\"a\" \"b\"]\n"),
+            "{\n  \"name\": \"John\",\n  \"tags\": [\"a\" ,\"b\"]\n}"
+        );
+        // A comment after a value goes, its line break stays
+        assert_eq!(
+            minimal("{\"a\": 1, // c\n \"b\": 2}"),
+            "{\"a\": 1, \n \"b\": 2}"
+        );
+        assert_eq!(
+            minimal("Here you go:\n```json\n{\"a\":  True}\n```\nThanks!\n"),
+            "{\"a\":  true}\n"
+        );
+        assert_eq!(minimal("[1, 2, 3"), "[1, 2, 3]");
+        assert_eq!(minimal("  "), "  {}");
+        assert_eq!(
+            repair_json(&minimal("{\"a\": [1, {\"b\": 'x"), &options).unwrap(),
+            r#"{"a":[1,{"b":"x"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let options = RepairOptions {
+            source_map: true,
+            ..Default::default()
+        };
+        let input = "Here: {users: [{name: 'Ann', age: 30}, {name: \"Bob\"";
+        let report = repair_json_with_report(input, &options).unwrap();
+        let map = report.source_map.unwrap();
+        let text = |pointer: &str| &input[map.get(pointer).unwrap()];
+        assert_eq!(text("/users/0/name"), "'Ann'");
+        assert_eq!(text("/users/0/age"), "30");
+        assert_eq!(text("/users/0"), "{name: 'Ann', age: 30}");
+        assert_eq!(text("/users/1"), "{name: \"Bob\"");
+        assert_eq!(text(""), &input[6..]);
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.get("/users/2"), None);
+
+        // Valid JSON is mapped too, and nothing is mapped without the option
+        let report = repair_json_with_report(r#"{"a": [true]}"#, &options).unwrap();
+        assert!(report.repairs.is_empty());
+        assert_eq!(report.source_map.unwrap().get("/a/0"), Some(7..11));
+        let report = repair_json_with_report(input, &RepairOptions::default()).unwrap();
+        assert_eq!(report.source_map, None);
+    }
+
+    #[test]
+    fn test_repair_bytes() {
+        let options = RepairOptions {
+            ensure_ascii: false,
+            ..Default::default()
+        };
+
+        // Windows-1252 mojibake from a mixed-source log
+        let repaired =
+            repair_bytes(b"{\"name\": \"Jos\xE9\", \"price\": \"\x8050\"", &options).unwrap();
+        assert_eq!(repaired.json, r#"{"name":"José","price":"€50"}"#);
+        assert_eq!(repaired.encoding, Encoding::Windows1252);
+
+        // UTF-16LE with a byte order mark
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("[1, 'два'".encode_utf16().flat_map(u16::to_le_bytes));
+        let repaired = repair_bytes(&utf16, &options).unwrap();
+        assert_eq!(repaired.json, r#"[1,"два"]"#);
+        assert_eq!(
+            repaired.decisions,
+            vec![DecodingDecision::BomStripped(Encoding::Utf16Le)]
+        );
+
+        // Invalid bytes within UTF-8 text become U+FFFD
+        let repaired = repair_bytes(b"[\"caf\xC3\xA9\", \"\xFF\"]", &options).unwrap();
+        assert_eq!(repaired.json, "[\"café\",\"\u{FFFD}\"]");
+        assert_eq!(
+            repaired.decisions,
+            vec![DecodingDecision::Replaced { offset: 11, len: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_load_invalid_utf8() {
+        let options = RepairOptions::default();
+        let result = load(&b"{\"a\": \"\xFF\"}"[..], &options);
+        assert!(matches!(result, Err(JsonRepairError::Utf8Error(_))));
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(b"[\xFF]").unwrap();
+        let result = from_file(temp_file.path(), &options);
+        assert!(matches!(result, Err(JsonRepairError::Utf8Error(_))));
+    }
+
+    /// Reader handing out one byte at a time, to split characters across reads
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((byte, rest)) if !buf.is_empty() => {
+                    buf[0] = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_repair_to_writer() {
+        let options = RepairOptions {
+            ensure_ascii: false,
+            ..Default::default()
+        };
+
+        for broken in [
+            r#"{name: 'John', age: 30, tags: ['a', 'b',"#,
+            r#"Here's the JSON: [{"city": "Zürich"}, {"city": "東京"}"#,
+            "```json\n{\"a\": [1, 2, 3]}\n```",
+        ] {
+            let mut output = Vec::new();
+            repair_to_writer(ByteReader(broken.as_bytes()), &mut output, &options).unwrap();
+            let streamed: Value = serde_json::from_slice(&output).unwrap();
+            assert_eq!(streamed, loads(broken, &options).unwrap());
+        }
+
+        // Invalid UTF-8 is reported instead of being silently dropped
+        let mut output = Vec::new();
+        let result = repair_to_writer(&b"[\"a\xff\"]"[..], &mut output, &options);
+        assert!(matches!(result, Err(JsonRepairError::Utf8Error(_))));
+    }
+
+    #[test]
+    fn test_repair_to_writer_large_input() {
+        let mut broken = String::from("{items: [");
+        for i in 0..10_000 {
+            broken.push_str(&format!("{{id: {}, name: 'item {}'}},\n", i, i));
+        }
+
+        let mut output = Vec::new();
+        repair_to_writer(broken.as_bytes(), &mut output, &RepairOptions::default()).unwrap();
+        let value: Value = serde_json::from_slice(&output).unwrap();
+        let items = value["items"].as_array().unwrap();
+        assert_eq!(items.len(), 10_000);
+        assert_eq!(items[9_999]["name"], "item 9999");
+    }
+
+    #[test]
+    fn test_repair_to_writer_deep_nesting() {
+        let depth = 100_000;
+        let broken = "[".repeat(depth);
+
+        let mut output = Vec::new();
+        repair_to_writer(broken.as_bytes(), &mut output, &RepairOptions::default()).unwrap();
+        assert_eq!(output.len(), depth * 2);
+        assert!(output.starts_with(b"[[") && output.ends_with(b"]]"));
+    }
+
     #[test]
     fn test_skip_validation() {
-        let mut options = RepairOptions::default();
-        options.skip_json_loads = true;
+        let options = RepairOptions {
+            skip_json_loads: true,
+            ..Default::default()
+        };
 
         // This should work even if the result isn't valid JSON
         let result = repair_json(r#"{name: "John"}"#, &options);
@@ -1001,8 +1572,10 @@ mod tests {
 
     #[test]
     fn test_performance_options() {
-        let mut options = RepairOptions::default();
-        options.skip_json_loads = true;
+        let options = RepairOptions {
+            skip_json_loads: true,
+            ..Default::default()
+        };
 
         let start = std::time::Instant::now();
         let _result = repair_json(r#"{name: "John", age: 30}"#, &options).unwrap();
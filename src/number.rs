@@ -0,0 +1,403 @@
+//! Numbers written the way people write them rather than the way JSON does
+
+/// Currency symbols recognised before or after a number
+const CURRENCY_SYMBOLS: [char; 5] = ['$', '€', '£', '¥', '₹'];
+/// Magnitude suffixes and the power of ten they stand for
+const MAGNITUDES: [(&str, i64); 6] = [("bn", 9), ("k", 3), ("K", 3), ("M", 6), ("B", 9), ("T", 12)];
+/// Words after a number that are values of their own rather than its unit
+const LITERALS: [&str; 5] = ["true", "false", "null", "none", "undefined"];
+
+/// How to repair a number written in a way JSON does not allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberRepair {
+    /// Rewrite it as the JSON number it stands for
+    Number,
+    /// Keep it as written, in a string
+    String,
+    /// Fail with [`JsonRepairError::InvalidNumber`](crate::JsonRepairError::InvalidNumber)
+    Error,
+}
+
+/// How each way of writing a number that JSON does not allow is repaired
+///
+/// Notations that only change how the value is spelled are rewritten as numbers by
+/// default. Numbers with a percent sign, a currency, a magnitude suffix or a unit stay
+/// strings, as a number would drop or reinterpret part of what was written.
+///
+/// A comma inside a number is read as thousands grouping when it is followed by exactly
+/// three digits, as in `1,000,000`, and as a decimal comma otherwise, as in `3,14`.
+/// Inside arrays, where commas separate elements, `[1,234]` stays two numbers; only
+/// groups that cannot be numbers of their own, as in `[1,000]`, are read as grouping,
+/// and decimal commas are never read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberOptions {
+    /// Leading zeros, as in `007`
+    pub leading_zeros: NumberRepair,
+    /// A plus sign, as in `+5`
+    pub plus_sign: NumberRepair,
+    /// A decimal point without digits before it, as in `.5`
+    pub bare_decimal_point: NumberRepair,
+    /// Thousands separated by commas, as in `1,000,000`
+    pub thousands_separators: NumberRepair,
+    /// A comma as decimal separator, as in `3,14`
+    pub decimal_comma: NumberRepair,
+    /// A percent sign, as in `30%`, which becomes the fraction `0.3`
+    pub percent: NumberRepair,
+    /// A currency symbol, as in `$12.50`, which is dropped
+    pub currency: NumberRepair,
+    /// A magnitude suffix, as in `1.2k`, `3M`, `2B` or `1T`, which is multiplied out
+    pub magnitude_suffix: NumberRepair,
+    /// A unit after the number, as in `42 years` or `5kg`, which is dropped
+    pub unit: NumberRepair,
+}
+
+impl Default for NumberOptions {
+    fn default() -> Self {
+        Self {
+            leading_zeros: NumberRepair::Number,
+            plus_sign: NumberRepair::Number,
+            bare_decimal_point: NumberRepair::Number,
+            thousands_separators: NumberRepair::Number,
+            decimal_comma: NumberRepair::Number,
+            percent: NumberRepair::String,
+            currency: NumberRepair::String,
+            magnitude_suffix: NumberRepair::String,
+            unit: NumberRepair::String,
+        }
+    }
+}
+
+impl NumberOptions {
+    /// How a number written with `notation` is repaired
+    pub(crate) fn repair_for(&self, notation: Notation) -> NumberRepair {
+        match notation {
+            // Completing a number cut short is always right
+            Notation::Incomplete => NumberRepair::Number,
+            Notation::LeadingZeros => self.leading_zeros,
+            Notation::PlusSign => self.plus_sign,
+            Notation::BareDecimalPoint => self.bare_decimal_point,
+            Notation::ThousandsSeparators => self.thousands_separators,
+            Notation::DecimalComma => self.decimal_comma,
+            Notation::Percent => self.percent,
+            Notation::Currency => self.currency,
+            Notation::MagnitudeSuffix => self.magnitude_suffix,
+            Notation::Unit => self.unit,
+        }
+    }
+}
+
+/// A way of writing a number that JSON does not allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Notation {
+    /// A decimal point or exponent without digits after it, as in `1.` or `1e`
+    Incomplete,
+    LeadingZeros,
+    PlusSign,
+    BareDecimalPoint,
+    ThousandsSeparators,
+    DecimalComma,
+    Percent,
+    Currency,
+    MagnitudeSuffix,
+    Unit,
+}
+
+impl Notation {
+    /// Whether the JSON number leaves out part of what was written
+    pub(crate) fn drops_text(self) -> bool {
+        matches!(self, Notation::Currency | Notation::Unit)
+    }
+}
+
+/// A number read from the input
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScannedNumber {
+    /// Length in bytes of the number as written, unit included
+    pub(crate) len: usize,
+    /// The number as JSON
+    pub(crate) json: String,
+    /// Notations JSON does not allow, empty for a valid JSON number
+    pub(crate) notations: Vec<Notation>,
+}
+
+/// Whether `ch` can start a number, in JSON or otherwise
+pub(crate) fn may_start_number(ch: char) -> bool {
+    ch.is_ascii_digit() || matches!(ch, '-' | '+' | '.') || CURRENCY_SYMBOLS.contains(&ch)
+}
+
+/// Read the number at the start of `text`, `None` when there is no digit to read
+///
+/// `in_array` tells that commas separate elements, see [`NumberOptions`].
+pub(crate) fn scan(text: &str, in_array: bool) -> Option<ScannedNumber> {
+    let at = |i: usize| text[i..].chars().next();
+    let digits_from = |i: usize| text[i..].bytes().take_while(u8::is_ascii_digit).count();
+    let currency_at = |i: usize| at(i).filter(|ch| CURRENCY_SYMBOLS.contains(ch));
+    let mut notations = Vec::new();
+    let mut i = 0;
+
+    // The currency symbol goes before or after the sign, as in `$-5` or `-$5`
+    if let Some(symbol) = currency_at(i) {
+        notations.push(Notation::Currency);
+        i += symbol.len_utf8();
+    }
+    let negative = at(i) == Some('-');
+    if negative {
+        i += 1;
+    } else if at(i) == Some('+') {
+        notations.push(Notation::PlusSign);
+        i += 1;
+    }
+    if !notations.contains(&Notation::Currency)
+        && let Some(symbol) = currency_at(i)
+    {
+        notations.push(Notation::Currency);
+        i += symbol.len_utf8();
+    }
+
+    let mut int = text[i..i + digits_from(i)].to_string();
+    i += int.len();
+
+    // Groups of exactly three digits after the first one to three digits
+    let group_at = |i: usize| text[i..].starts_with(',') && digits_from(i + 1) == 3;
+    let mut grouped = false;
+    if (1..=3).contains(&int.len()) && !int.starts_with('0') && group_at(i) {
+        let mut end = i;
+        while group_at(end) {
+            end += 4;
+        }
+        let groups: Vec<&str> = text[i..end].split(',').skip(1).collect();
+        // In arrays, `[1,234]` is two valid numbers while `[1,000]` cannot be
+        grouped = !in_array || groups.iter().any(|group| group.starts_with('0'));
+        if grouped {
+            notations.push(Notation::ThousandsSeparators);
+            int.extend(groups);
+            i = end;
+        }
+    }
+
+    let mut frac = None;
+    if !in_array && !grouped && !int.is_empty() && text[i..].starts_with(',') {
+        let len = digits_from(i + 1);
+        if len > 0 {
+            notations.push(Notation::DecimalComma);
+            frac = Some(&text[i + 1..i + 1 + len]);
+            i += 1 + len;
+        }
+    }
+    if frac.is_none() && at(i) == Some('.') {
+        let len = digits_from(i + 1);
+        frac = Some(&text[i + 1..i + 1 + len]);
+        i += 1 + len;
+        if len == 0 {
+            notations.push(Notation::Incomplete);
+        } else if int.is_empty() {
+            notations.push(Notation::BareDecimalPoint);
+        }
+    }
+    if int.is_empty() && frac.is_none_or(str::is_empty) {
+        return None;
+    }
+    if int.len() > 1 && int.starts_with('0') {
+        notations.push(Notation::LeadingZeros);
+    }
+
+    // An exponent, possibly cut short at the end of the value
+    let mut exponent = String::new();
+    if let Some(marker @ ('e' | 'E')) = at(i) {
+        let sign = usize::from(matches!(at(i + 1), Some('+' | '-')));
+        let len = digits_from(i + 1 + sign);
+        let ends =
+            at(i + 1 + sign).is_none_or(|ch| ch.is_whitespace() || matches!(ch, ',' | '}' | ']'));
+        if len > 0 || ends {
+            exponent.push(marker);
+            exponent.push_str(&text[i + 1..i + 1 + sign + len]);
+            i += 1 + sign + len;
+            if len == 0 {
+                notations.push(Notation::Incomplete);
+                exponent.push('0');
+            }
+        }
+    }
+
+    let mut shift = 0;
+    if exponent.is_empty()
+        && let Some((suffix, power)) = MAGNITUDES.iter().find(|(suffix, _)| {
+            text[i..].starts_with(suffix)
+                && !at(i + suffix.len()).is_some_and(char::is_alphanumeric)
+        })
+    {
+        notations.push(Notation::MagnitudeSuffix);
+        shift += power;
+        i += suffix.len();
+    }
+
+    let spaces = |i: usize| text[i..].len() - text[i..].trim_start_matches([' ', '\t']).len();
+    if text[i + spaces(i)..].starts_with('%') {
+        notations.push(Notation::Percent);
+        shift -= 2;
+        i += spaces(i) + 1;
+    } else if !notations.contains(&Notation::Currency)
+        && let Some(symbol) = currency_at(i + spaces(i))
+    {
+        notations.push(Notation::Currency);
+        i += spaces(i) + symbol.len_utf8();
+    }
+
+    // Words up to the end of the value, as in `42 years`
+    let word = &text[i + spaces(i)..];
+    if word.starts_with(char::is_alphabetic) {
+        let len = word
+            .find(|ch: char| !(ch.is_alphabetic() || matches!(ch, ' ' | '\t' | '.')))
+            .unwrap_or(word.len());
+        let ends =
+            at(i + spaces(i) + len).is_none_or(|ch| matches!(ch, ',' | '}' | ']' | '\n' | '\r'));
+        let first = word.split(|ch: char| !ch.is_alphabetic()).next();
+        if ends && !first.is_some_and(|first| LITERALS.contains(&first.to_lowercase().as_str())) {
+            notations.push(Notation::Unit);
+            i += spaces(i) + word[..len].trim_end().len();
+        }
+    }
+
+    let (int, frac) = if shift == 0 {
+        (int, frac.map(str::to_string))
+    } else {
+        shift_point(&int, frac.unwrap_or(""), shift)
+    };
+    let int = match int.trim_start_matches('0') {
+        "" => "0",
+        int => int,
+    };
+    let mut json = String::new();
+    if negative {
+        json.push('-');
+    }
+    json.push_str(int);
+    if let Some(frac) = frac {
+        json.push('.');
+        json.push_str(if frac.is_empty() { "0" } else { &frac });
+    }
+    json.push_str(&exponent);
+
+    Some(ScannedNumber {
+        len: i,
+        json,
+        notations,
+    })
+}
+
+/// The digits of `int.frac` with the decimal point moved `shift` places to the right
+fn shift_point(int: &str, frac: &str, shift: i64) -> (String, Option<String>) {
+    let digits = format!("{}{}", int, frac);
+    let point = int.len() as i64 + shift;
+    let (int, frac) = if point <= 0 {
+        (
+            String::new(),
+            "0".repeat(point.unsigned_abs() as usize) + &digits,
+        )
+    } else if point as usize >= digits.len() {
+        (
+            digits.clone() + &"0".repeat(point as usize - digits.len()),
+            String::new(),
+        )
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        (int.to_string(), frac.to_string())
+    };
+    let frac = frac.trim_end_matches('0');
+    (int, (!frac.is_empty()).then(|| frac.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        use Notation::*;
+        fn read(text: &str, in_array: bool) -> (&str, String, Vec<Notation>) {
+            let number = scan(text, in_array).unwrap();
+            (&text[..number.len], number.json, number.notations)
+        }
+
+        assert_eq!(
+            read("-1.5E+3]", false),
+            ("-1.5E+3", "-1.5E+3".to_string(), vec![])
+        );
+        assert_eq!(
+            read("1. ", false),
+            ("1.", "1.0".to_string(), vec![Incomplete])
+        );
+        assert_eq!(
+            read("1e}", false),
+            ("1e", "1e0".to_string(), vec![Incomplete])
+        );
+        assert_eq!(
+            read("007,", false),
+            ("007", "7".to_string(), vec![LeadingZeros])
+        );
+        assert_eq!(read("+5}", false), ("+5", "5".to_string(), vec![PlusSign]));
+        assert_eq!(
+            read("-.5", false),
+            ("-.5", "-0.5".to_string(), vec![BareDecimalPoint])
+        );
+        assert_eq!(
+            read("1,000,000.5, \"b\"", false),
+            (
+                "1,000,000.5",
+                "1000000.5".to_string(),
+                vec![ThousandsSeparators]
+            )
+        );
+        assert_eq!(
+            read("3,14}", false),
+            ("3,14", "3.14".to_string(), vec![DecimalComma])
+        );
+        assert_eq!(
+            read("0,500}", false),
+            ("0,500", "0.500".to_string(), vec![DecimalComma])
+        );
+        assert_eq!(
+            read("30%,", false),
+            ("30%", "0.3".to_string(), vec![Percent])
+        );
+        assert_eq!(
+            read("12.5 %", false),
+            ("12.5 %", "0.125".to_string(), vec![Percent])
+        );
+        assert_eq!(
+            read("$12.50", false),
+            ("$12.50", "12.50".to_string(), vec![Currency])
+        );
+        assert_eq!(
+            read("-€3 ", false),
+            ("-€3", "-3".to_string(), vec![Currency])
+        );
+        assert_eq!(
+            read("1.2k,", false),
+            ("1.2k", "1200".to_string(), vec![MagnitudeSuffix])
+        );
+        assert_eq!(
+            read("42 years old,\n", false),
+            ("42 years old", "42".to_string(), vec![Unit])
+        );
+        assert_eq!(read("5kg}", false), ("5kg", "5".to_string(), vec![Unit]));
+
+        // Commas between array elements
+        assert_eq!(read("1,234]", true), ("1", "1".to_string(), vec![]));
+        assert_eq!(read("3,14]", true), ("3", "3".to_string(), vec![]));
+        assert_eq!(
+            read("1,000]", true),
+            ("1,000", "1000".to_string(), vec![ThousandsSeparators])
+        );
+
+        // What follows is not part of the number
+        assert_eq!(read("1 true]", true), ("1", "1".to_string(), vec![]));
+        assert_eq!(read("1 \"b\": 2", false), ("1", "1".to_string(), vec![]));
+        assert_eq!(read("2 b: 3", false), ("2", "2".to_string(), vec![]));
+        assert_eq!(read("5eggs: 1", false), ("5", "5".to_string(), vec![]));
+        assert!(scan("-", false).is_none());
+        assert!(scan("-abc", false).is_none());
+        assert!(scan("$", false).is_none());
+        assert!(scan(".", false).is_none());
+    }
+}
@@ -0,0 +1,1596 @@
+//! The repair parser
+//!
+//! The parser is a state machine driven by an explicit stack of open containers rather
+//! than by recursion, so deeply nested input cannot overflow the call stack and memory
+//! stays bounded by the nesting depth when reading from a stream.
+
+use crate::events::Event;
+use crate::input::{Input, LOOKAHEAD};
+use crate::number::{self, Notation, NumberRepair};
+use crate::source_map::Span;
+use crate::{JsonRepairError, Placeholders, Repair, RepairKind, RepairOptions, RootValues};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::ops::Range;
+
+/// Output buffered before it is flushed to a writer
+const FLUSH_THRESHOLD: usize = 8 * 1024;
+/// Words longer than this cannot be one of the recognised literals
+const MAX_LITERAL_LEN: usize = 16;
+/// Characters read past a number for its separators, suffixes and unit
+const NUMBER_TAIL: usize = 64;
+
+/// Parser state for an open container
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ParseState {
+    Object {
+        expecting_key: bool,
+        needs_comma: bool,
+    },
+    Array {
+        needs_comma: bool,
+    },
+}
+
+impl ParseState {
+    /// The delimiter that closes the container in JSON
+    fn closer(self) -> char {
+        match self {
+            ParseState::Object { .. } => '}',
+            ParseState::Array { .. } => ']',
+        }
+    }
+}
+
+/// Where the parser is inside an open container, for the paths of repairs
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// Key of the current member, `None` before the first key is known
+    Key(Option<String>),
+    /// Index of the current element, `None` before the first element
+    Index(Option<usize>),
+}
+
+/// How far the parser got through the document
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    /// Looking for the JSON and its first value
+    Start,
+    /// Inside the containers of the first value
+    Values,
+    Done,
+}
+
+/// Where [`JsonRepairParser::skip_to_json`] found the JSON
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum JsonStart {
+    /// A value starts at the current position
+    Value,
+    /// The members of a container whose opening delimiter is missing start there
+    Headless(ParseState),
+    /// Nowhere, the input holds nothing but whitespace and reasoning
+    Nothing,
+}
+
+/// JSON repair parser
+pub(crate) struct JsonRepairParser<'a> {
+    input: Input<'a>,
+    /// Absolute byte offset in the input
+    pos: usize,
+    output: String,
+    writer: Option<Box<dyn Write + 'a>>,
+    /// Open containers, empty at the root
+    state_stack: Vec<ParseState>,
+    /// Current member of each open container, parallel to `state_stack`
+    path: Vec<Segment>,
+    /// Span of each open container in `spans`, parallel to `state_stack`
+    open_spans: Vec<Option<usize>>,
+    /// Delimiter the input closes each open container with, parallel to `state_stack`
+    closers: Vec<char>,
+    options: RepairOptions,
+    /// Fixes applied so far, not recorded for reader input to keep memory bounded
+    repairs: Option<Vec<Repair>>,
+    /// Where each value came from, recorded with the `source_map` option
+    spans: Option<Vec<Span>>,
+    /// Events not pulled yet, when parsing into events instead of output
+    events: Option<VecDeque<Event>>,
+    /// Pieces of input and the output written for them, for minimal edits
+    alignment: Option<Vec<(Range<usize>, Range<usize>)>>,
+    /// Input and output positions the alignment reaches
+    aligned: (usize, usize),
+    phase: Phase,
+    /// Input positions of the top-level values found, when looking for all of them
+    root_starts: Vec<usize>,
+    /// Whether the top-level values are wrapped in an array the parser opened
+    wrapping: bool,
+    /// Input position right after the last comma between array elements
+    after_comma: Option<usize>,
+    error: Option<JsonRepairError>,
+}
+
+impl<'a> JsonRepairParser<'a> {
+    pub(crate) fn new(input: &str, options: RepairOptions) -> Self {
+        Self::with_input(Input::from_str(input), None, options)
+    }
+
+    /// Parser that reads `reader` through a bounded window and streams its output to `writer`
+    pub(crate) fn from_reader<R: Read + 'a, W: Write + 'a>(
+        reader: R,
+        writer: W,
+        options: RepairOptions,
+    ) -> Self {
+        Self::with_input(Input::from_reader(reader), Some(Box::new(writer)), options)
+    }
+
+    fn with_input(
+        input: Input<'a>,
+        writer: Option<Box<dyn Write + 'a>>,
+        options: RepairOptions,
+    ) -> Self {
+        let repairs = writer.is_none().then(Vec::new);
+        let spans = (writer.is_none() && options.source_map).then(Vec::new);
+        Self {
+            input,
+            pos: 0,
+            output: String::new(),
+            writer,
+            state_stack: Vec::new(),
+            path: Vec::new(),
+            open_spans: Vec::new(),
+            closers: Vec::new(),
+            options,
+            repairs,
+            spans,
+            events: None,
+            alignment: None,
+            aligned: (0, 0),
+            phase: Phase::Start,
+            root_starts: Vec::new(),
+            wrapping: false,
+            after_comma: None,
+            error: None,
+        }
+    }
+
+    /// Parser that also aligns its output with the input, see [`Self::take_alignment`]
+    pub(crate) fn aligned(input: &'a str, options: RepairOptions) -> Self {
+        let mut parser = Self::new(input, options);
+        parser.alignment = Some(Vec::new());
+        parser
+    }
+
+    /// Parser that turns the input into events, pulled with [`Self::next_event`]
+    pub(crate) fn event_parser(input: Input<'a>, options: RepairOptions) -> Self {
+        let mut parser = Self::with_input(input, None, options);
+        parser.repairs = None;
+        parser.spans = None;
+        parser.events = Some(VecDeque::new());
+        parser
+    }
+
+    fn current_char(&mut self) -> Option<char> {
+        self.input.char_at(self.pos)
+    }
+
+    fn peek_char(&mut self, offset: usize) -> Option<char> {
+        let mut pos = self.pos;
+        for _ in 0..offset {
+            pos += self.input.char_at(pos)?.len_utf8();
+        }
+        self.input.char_at(pos)
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let ch = self.current_char();
+        if let Some(ch) = ch {
+            self.pos += ch.len_utf8();
+        }
+        ch
+    }
+
+    /// Record a fix at the current position
+    fn repair(&mut self, kind: RepairKind) {
+        self.repair_at(kind, self.pos);
+    }
+
+    fn repair_at(&mut self, kind: RepairKind, offset: usize) {
+        if !self.records_repairs() {
+            return;
+        }
+        // Closing a container is about the container, not its last member
+        let depth = match kind {
+            RepairKind::ClosedObject | RepairKind::ClosedArray => self.path.len().saturating_sub(1),
+            _ => self.path.len(),
+        };
+        let repair = Repair {
+            kind,
+            offset,
+            path: self.pointer(depth),
+        };
+        if let Some(events) = self.events.as_mut() {
+            events.push_back(Event::Repair(repair.clone()));
+        }
+        if let Some(repairs) = self.repairs.as_mut() {
+            repairs.push(repair);
+        }
+    }
+
+    /// Whether fixes are recorded, in the report or as events
+    fn records_repairs(&self) -> bool {
+        self.repairs.is_some() || self.events.is_some()
+    }
+
+    fn emit(&mut self, event: Event) {
+        if let Some(events) = self.events.as_mut() {
+            events.push_back(event);
+        }
+    }
+
+    /// Emit the scalar written to the output since `output_start`
+    fn emit_scalar(&mut self, output_start: usize) {
+        if self.events.is_none() {
+            return;
+        }
+        let event = match serde_json::from_str(&self.output[output_start..]) {
+            Ok(Value::Bool(value)) => Event::Bool(value),
+            Ok(Value::Number(number)) => Event::Number(number),
+            Ok(Value::String(string)) => Event::String(string),
+            Ok(_) => Event::Null,
+            Err(_) => Event::String(self.output[output_start..].to_string()),
+        };
+        self.emit(event);
+    }
+
+    /// JSON Pointer to the current member of the `depth` outermost containers
+    fn pointer(&self, depth: usize) -> String {
+        let mut pointer = String::new();
+        for segment in &self.path[..depth] {
+            match segment {
+                Segment::Key(Some(key)) => {
+                    pointer.push('/');
+                    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                }
+                Segment::Index(Some(index)) => {
+                    pointer.push('/');
+                    pointer.push_str(&index.to_string());
+                }
+                Segment::Key(None) | Segment::Index(None) => break,
+            }
+        }
+        pointer
+    }
+
+    /// Open a container that the input closes with `closer`
+    fn push_container(&mut self, state: ParseState, closer: char, span: Option<usize>) {
+        self.path.push(match state {
+            ParseState::Object { .. } => Segment::Key(None),
+            ParseState::Array { .. } => Segment::Index(None),
+        });
+        self.open_spans.push(span);
+        self.closers.push(closer);
+        self.state_stack.push(state);
+    }
+
+    fn pop_container(&mut self) {
+        match self.state_stack.pop() {
+            Some(ParseState::Object { .. }) => self.emit(Event::EndObject),
+            Some(ParseState::Array { .. }) => self.emit(Event::EndArray),
+            None => {}
+        }
+        self.path.pop();
+        self.closers.pop();
+        if let Some(span) = self.open_spans.pop() {
+            self.end_span(span);
+        }
+    }
+
+    /// Start the span of a value at the current position, returning its index
+    fn start_span(&mut self) -> Option<usize> {
+        let pointer = self.spans.as_ref().map(|_| self.pointer(self.path.len()))?;
+        let (pos, output_len) = (self.pos, self.output.len());
+        let spans = self.spans.as_mut()?;
+        spans.push(Span {
+            pointer,
+            input: pos..pos,
+            output: output_len..output_len,
+        });
+        Some(spans.len() - 1)
+    }
+
+    /// End the span started by [`Self::start_span`] at the current position
+    fn end_span(&mut self, index: Option<usize>) {
+        let (pos, output_len) = (self.pos, self.output.len());
+        if let (Some(spans), Some(index)) = (self.spans.as_mut(), index) {
+            spans[index].input.end = pos;
+            spans[index].output.end = output_len;
+        }
+    }
+
+    fn set_segment(&mut self, segment: Segment) {
+        if let Some(top) = self.path.last_mut() {
+            *top = segment;
+        }
+    }
+
+    fn update_state(&mut self, state: ParseState) {
+        if let Some(top) = self.state_stack.last_mut() {
+            *top = state;
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        // Whitespace is aligned on its own, so that minimal edits keep it
+        self.align();
+        while let Some(ch) = self.current_char() {
+            if ch.is_whitespace() {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        self.align();
+    }
+
+    /// Record that the input read since the last call became the output written since
+    fn align(&mut self) {
+        let (pos, output_len) = (self.pos, self.output.len());
+        let (aligned_pos, aligned_len) = self.aligned;
+        let Some(alignment) = self.alignment.as_mut() else {
+            return;
+        };
+        if pos > aligned_pos || output_len > aligned_len {
+            alignment.push((aligned_pos..pos.max(aligned_pos), aligned_len..output_len));
+            self.aligned = (pos.max(aligned_pos), output_len);
+        }
+    }
+
+    fn skip_comments(&mut self) {
+        if self.current_char() != Some('/') {
+            return;
+        }
+        if matches!(self.peek_char(1), Some('/' | '*')) {
+            self.repair(RepairKind::RemovedComment);
+        }
+        if let (Some('/'), Some('/')) = (self.current_char(), self.peek_char(1)) {
+            // Skip line comment, leaving its newline to the whitespace around it
+            while self.current_char().is_some_and(|ch| ch != '\n') {
+                self.advance();
+            }
+        } else if let (Some('/'), Some('*')) = (self.current_char(), self.peek_char(1)) {
+            // Skip block comment
+            self.advance(); // skip '/'
+            self.advance(); // skip '*'
+            while let Some(ch) = self.advance() {
+                if ch == '*' && self.peek_char(0) == Some('/') {
+                    self.advance(); // skip '/'
+                    break;
+                }
+            }
+        }
+    }
+
+    fn append_char(&mut self, ch: char) {
+        self.output.push(ch);
+        self.flush_if_full();
+    }
+
+    fn append_str(&mut self, s: &str) {
+        self.output.push_str(s);
+        self.flush_if_full();
+    }
+
+    /// Append a character of a string's content, escaping it if needed
+    fn append_string_char(&mut self, ch: char) {
+        match ch {
+            ' '..='~' => self.append_char(ch),
+            '\0'..='\u{1f}' => {
+                self.repair(RepairKind::EscapedCharacter);
+                match ch {
+                    '\n' => self.append_str("\\n"),
+                    '\r' => self.append_str("\\r"),
+                    '\t' => self.append_str("\\t"),
+                    _ => self.append_str(&format!("\\u{:04x}", ch as u32)),
+                }
+            }
+            _ if !self.options.ensure_ascii => self.append_char(ch),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    self.output.push_str(&format!("\\u{:04x}", unit));
+                }
+                self.flush_if_full();
+            }
+        }
+    }
+
+    fn flush_if_full(&mut self) {
+        if self.writer.is_some() && self.output.len() >= FLUSH_THRESHOLD {
+            self.flush();
+        }
+    }
+
+    /// Hand buffered output to the writer, if any
+    fn flush(&mut self) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        if let Err(e) = writer.write_all(self.output.as_bytes()) {
+            self.error.get_or_insert(e.into());
+        }
+        self.output.clear();
+    }
+
+    fn parse_string(&mut self) -> Result<(), JsonRepairError> {
+        let quote_char = if self.current_char() == Some('"') {
+            '"'
+        } else if self.current_char() == Some('\'') {
+            self.repair(RepairKind::ReplacedQuotes);
+            '\''
+        } else {
+            // Unquoted string - add quotes
+            self.repair(RepairKind::QuotedString);
+            self.append_char('"');
+            return self.parse_unquoted_string();
+        };
+
+        self.append_char('"'); // Always use double quotes in output
+        self.advance(); // Skip opening quote
+
+        while let Some(ch) = self.current_char() {
+            if ch == quote_char {
+                self.advance();
+                self.append_char('"');
+                return Ok(());
+            } else if ch == '\\' {
+                self.advance();
+                match self.current_char() {
+                    // A control character cannot be escaped, so the backslash is text
+                    Some(escaped @ '\0'..='\u{1f}') => {
+                        self.append_str("\\\\");
+                        self.append_string_char(escaped);
+                        self.advance();
+                    }
+                    Some(escaped) => {
+                        self.append_char(ch);
+                        self.append_char(escaped);
+                        self.advance();
+                    }
+                    None => self.append_str("\\\\"),
+                }
+            } else if ch == '"' && quote_char == '\'' {
+                // Escape double quotes inside single-quoted strings
+                self.repair(RepairKind::EscapedCharacter);
+                self.append_str("\\\"");
+                self.advance();
+            } else {
+                self.append_string_char(ch);
+                self.advance();
+            }
+        }
+
+        // Unclosed string - close it
+        self.repair(RepairKind::ClosedString);
+        self.append_char('"');
+        Ok(())
+    }
+
+    fn parse_unquoted_string(&mut self) -> Result<(), JsonRepairError> {
+        while let Some(ch) = self.current_char() {
+            match ch {
+                ',' | '}' | ']' | ':' => break,
+                '"' => {
+                    self.append_str("\\\"");
+                    self.advance();
+                }
+                '\\' => {
+                    self.append_str("\\\\");
+                    self.advance();
+                }
+                _ if ch.is_whitespace() => {
+                    // Check if this is trailing whitespace
+                    let mut temp_pos = self.pos + ch.len_utf8();
+                    let mut found_delimiter = false;
+                    while let Some(temp_ch) = self.input.char_at(temp_pos) {
+                        if matches!(temp_ch, ',' | '}' | ']' | ':') {
+                            found_delimiter = true;
+                            break;
+                        } else if !temp_ch.is_whitespace() {
+                            break;
+                        }
+                        temp_pos += temp_ch.len_utf8();
+                    }
+
+                    if found_delimiter {
+                        break; // Stop at trailing whitespace
+                    } else {
+                        self.append_string_char(ch);
+                        self.advance();
+                    }
+                }
+                _ => {
+                    self.append_string_char(ch);
+                    self.advance();
+                }
+            }
+        }
+        self.append_char('"');
+        Ok(())
+    }
+
+    fn parse_number(&mut self) -> Result<(), JsonRepairError> {
+        // The number is read in full; past it, only enough for separators and a unit
+        let mut text = String::new();
+        let mut end = self.pos;
+        let mut tail = 0;
+        while tail < NUMBER_TAIL
+            && let Some(ch) = self.input.char_at(end)
+        {
+            if tail > 0
+                || !(text.is_empty()
+                    || ch.is_ascii_digit()
+                    || matches!(ch, '.' | 'e' | 'E' | '+' | '-'))
+            {
+                tail += 1;
+            }
+            text.push(ch);
+            end += ch.len_utf8();
+        }
+
+        let in_array = matches!(self.state_stack.last(), Some(ParseState::Array { .. }));
+        let number = number::scan(&text, in_array).filter(|number| {
+            !text[number.len..].starts_with(|ch: char| ch.is_alphanumeric() || ch == '_')
+        });
+        let Some(number) = number else {
+            // A sign or symbol that starts nothing numeric is text, and so is a number
+            // glued to more of a word, as in `0x10` or `1_000`
+            self.repair(RepairKind::QuotedString);
+            self.append_char('"');
+            return self.parse_unquoted_string();
+        };
+
+        let written = &text[..number.len];
+        // Digits glued to an array comma, as in `[12345,000]`, are likely part of a number
+        // grouped in a way not read; dropping their zeros would lose it
+        let mut as_string = in_array
+            && self.after_comma == Some(self.pos)
+            && number.notations.contains(&Notation::LeadingZeros);
+        for &notation in &number.notations {
+            match self.options.numbers.repair_for(notation) {
+                NumberRepair::Number => {}
+                NumberRepair::String => as_string = true,
+                NumberRepair::Error => {
+                    return Err(JsonRepairError::InvalidNumber {
+                        text: written.to_string(),
+                        offset: self.pos,
+                    });
+                }
+            }
+        }
+
+        if as_string {
+            self.repair(RepairKind::QuotedString);
+            self.append_char('"');
+            for ch in written.chars() {
+                self.append_string_char(ch);
+            }
+            self.append_char('"');
+        } else {
+            if number
+                .notations
+                .iter()
+                .any(|notation| notation.drops_text())
+            {
+                self.repair(RepairKind::DroppedUnit);
+            }
+            if number
+                .notations
+                .iter()
+                .any(|notation| !notation.drops_text())
+            {
+                self.repair(RepairKind::NormalizedNumber);
+            }
+            self.append_str(&number.json);
+        }
+        self.pos += number.len;
+        Ok(())
+    }
+
+    fn parse_literal(&mut self) -> Result<(), JsonRepairError> {
+        // Look ahead without consuming, so unknown words can be parsed as strings
+        let mut literal = String::new();
+        let mut end = self.pos;
+        while let Some(ch) = self.input.char_at(end) {
+            if matches!(ch, ',' | '}' | ']' | ':')
+                || ch.is_whitespace()
+                || literal.len() >= MAX_LITERAL_LEN
+            {
+                break;
+            }
+            literal.push(ch);
+            end += ch.len_utf8();
+        }
+
+        let replacement = match literal.to_lowercase().as_str() {
+            "true" => "true",
+            "false" => "false",
+            "null" | "none" | "undefined" => "null",
+            _ => {
+                // Treat as unquoted string
+                self.repair(RepairKind::QuotedString);
+                self.append_char('"');
+                return self.parse_unquoted_string();
+            }
+        };
+        if literal != replacement {
+            self.repair(RepairKind::NormalizedLiteral);
+        }
+        self.pos = end;
+        self.append_str(replacement);
+        Ok(())
+    }
+
+    /// Parse a value, opening a container or emitting a scalar
+    fn parse_value(&mut self) -> Result<(), JsonRepairError> {
+        self.skip_whitespace();
+        self.skip_comments_in_slot(true);
+        self.skip_whitespace();
+
+        let span = self.start_span();
+        let depth = self.state_stack.len();
+        let output_start = self.output.len();
+        match self.current_char() {
+            Some(_) if self.replace_placeholder()? => {}
+            None => {
+                // End of input - provide default value
+                self.repair(RepairKind::InsertedValue);
+                self.append_str("null");
+            }
+            Some('"') | Some('\'') => {
+                self.parse_string()?;
+            }
+            Some(ch) if number::may_start_number(ch) => {
+                self.parse_number()?;
+            }
+            Some(closer @ ('{' | '[')) => {
+                let closer = if closer == '{' { '}' } else { ']' };
+                let object = if closer == '}' {
+                    !self.holds_bare_values()
+                } else {
+                    self.holds_pairs()
+                };
+                if object != (closer == '}') {
+                    self.repair(if object {
+                        RepairKind::ConvertedToObject
+                    } else {
+                        RepairKind::ConvertedToArray
+                    });
+                }
+                self.advance();
+                let state = if object {
+                    self.append_char('{');
+                    self.emit(Event::StartObject);
+                    ParseState::Object {
+                        expecting_key: true,
+                        needs_comma: false,
+                    }
+                } else {
+                    self.append_char('[');
+                    self.emit(Event::StartArray);
+                    ParseState::Array { needs_comma: false }
+                };
+                self.push_container(state, closer, span);
+            }
+            Some(ch) if ch.is_alphabetic() => {
+                self.parse_literal()?;
+            }
+            Some(ch) => {
+                // Invalid character - treat as unquoted string, which is empty at a
+                // delimiter
+                self.repair(if matches!(ch, ',' | '}' | ']' | ':') {
+                    RepairKind::InsertedValue
+                } else {
+                    RepairKind::QuotedString
+                });
+                self.append_char('"');
+                self.parse_unquoted_string()?;
+            }
+        }
+
+        // Containers end their span and emit their events when they close
+        if self.state_stack.len() == depth {
+            self.end_span(span);
+            self.emit_scalar(output_start);
+        }
+        Ok(())
+    }
+
+    /// Parse the next member of the innermost object, or close it
+    fn parse_object(
+        &mut self,
+        mut expecting_key: bool,
+        mut needs_comma: bool,
+    ) -> Result<(), JsonRepairError> {
+        let pos_before = self.pos; // Safety check for infinite loops
+
+        self.skip_whitespace();
+        self.skip_comments_in_slot(expecting_key);
+        self.skip_whitespace();
+
+        if needs_comma
+            && !expecting_key
+            && let Some(ch) = self.current_char()
+            && (ch == '"' || ch == '\'' || ch.is_alphabetic() || ch == '_')
+        {
+            self.repair(RepairKind::InsertedComma);
+            self.append_char(',');
+            expecting_key = true;
+            needs_comma = false;
+        }
+
+        match self.current_char() {
+            None => {
+                // Incomplete object - close it
+                self.repair(RepairKind::ClosedObject);
+                self.append_char('}');
+                self.pop_container();
+                return Ok(());
+            }
+            Some(ch @ ('}' | ']')) => {
+                self.parse_closer(ch);
+                return Ok(());
+            }
+            Some(',') => {
+                let comma_at = self.pos;
+                self.advance();
+                // Skip trailing or multiple commas, and those before a dropped placeholder
+                let next = self.skip_whitespace_at(self.pos);
+                if !matches!(self.input.char_at(next), Some('}' | ']') | None)
+                    && !self.drops_placeholder(next)
+                    && !expecting_key
+                {
+                    self.append_char(',');
+                    expecting_key = true;
+                    needs_comma = false;
+                } else {
+                    self.repair_at(RepairKind::RemovedComma, comma_at);
+                }
+                self.update_state(ParseState::Object {
+                    expecting_key,
+                    needs_comma,
+                });
+                return Ok(());
+            }
+            _ if self.drop_placeholder()? => {}
+            _ => {
+                if needs_comma {
+                    self.repair(RepairKind::InsertedComma);
+                    self.append_char(',');
+                }
+
+                if expecting_key {
+                    self.set_segment(Segment::Key(None));
+                    let key_start = self.output.len();
+                    let repairs_before = self.repairs.as_ref().map_or(0, Vec::len);
+                    let events_before = self.events.as_ref().map_or(0, VecDeque::len);
+
+                    // Parse key
+                    if matches!(self.current_char(), Some('"') | Some('\'')) {
+                        self.parse_string()?;
+                    } else {
+                        // Unquoted key
+                        self.repair(RepairKind::QuotedKey);
+                        self.append_char('"');
+                        self.parse_unquoted_string()?;
+                    }
+
+                    if self.records_repairs() {
+                        // Fixes inside the key belong to the member it starts
+                        let key: Option<String> =
+                            serde_json::from_str(&self.output[key_start..]).ok();
+                        self.set_segment(Segment::Key(key.clone()));
+                        let path = self.pointer(self.path.len());
+                        if let Some(repairs) = self.repairs.as_mut() {
+                            for repair in &mut repairs[repairs_before..] {
+                                repair.path.clone_from(&path);
+                            }
+                        }
+                        if let Some(events) = self.events.as_mut() {
+                            for event in events.range_mut(events_before..) {
+                                if let Event::Repair(repair) = event {
+                                    repair.path.clone_from(&path);
+                                }
+                            }
+                        }
+                        self.emit(Event::Key(key.unwrap_or_default()));
+                    }
+
+                    // Expect colon
+                    self.skip_whitespace();
+                    if self.current_char() == Some(':') {
+                        self.advance();
+                    } else {
+                        self.repair(RepairKind::InsertedColon);
+                    }
+                    self.append_char(':');
+                } else {
+                    // We have a value but expected a key - this shouldn't happen
+                    // Add a default key
+                    self.set_segment(Segment::Key(Some("unknown".to_string())));
+                    self.repair(RepairKind::InventedKey);
+                    self.append_str("\"unknown\":");
+                }
+
+                self.update_state(ParseState::Object {
+                    expecting_key: false,
+                    needs_comma: true,
+                });
+                self.parse_value()?;
+            }
+        }
+
+        // Safety check: ensure we're making progress
+        if self.pos == pos_before && self.current_char().is_some() {
+            // We're stuck - advance one character to avoid infinite loop
+            self.repair(RepairKind::SkippedText);
+            self.advance();
+        }
+
+        Ok(())
+    }
+
+    /// Parse the next element of the innermost array, or close it
+    fn parse_array(&mut self, mut needs_comma: bool) -> Result<(), JsonRepairError> {
+        let pos_before = self.pos;
+        self.skip_whitespace();
+        self.skip_comments_in_slot(!needs_comma);
+        self.skip_whitespace();
+
+        match self.current_char() {
+            None => {
+                // Incomplete array - close it
+                self.repair(RepairKind::ClosedArray);
+                self.append_char(']');
+                self.pop_container();
+            }
+            Some(ch @ ('}' | ']')) => self.parse_closer(ch),
+            Some(':') => {
+                self.repair(RepairKind::SkippedText);
+                self.advance();
+            }
+            Some(',') => {
+                let comma_at = self.pos;
+                self.advance();
+                self.after_comma = Some(self.pos);
+                // Skip trailing or multiple commas, and those before a dropped placeholder
+                let next = self.skip_whitespace_at(self.pos);
+                if !matches!(self.input.char_at(next), Some(']' | '}') | None)
+                    && !self.drops_placeholder(next)
+                    && needs_comma
+                {
+                    self.append_char(',');
+                    needs_comma = false;
+                } else {
+                    self.repair_at(RepairKind::RemovedComma, comma_at);
+                }
+                self.update_state(ParseState::Array { needs_comma });
+            }
+            _ if self.drop_placeholder()? => {}
+            _ => {
+                if needs_comma {
+                    self.repair(RepairKind::InsertedComma);
+                    self.append_char(',');
+                }
+
+                if let Some(Segment::Index(index)) = self.path.last_mut() {
+                    *index = Some(index.map_or(0, |index| index + 1));
+                }
+                self.update_state(ParseState::Array { needs_comma: true });
+                self.parse_value()?;
+
+                // Safety check: ensure we're making progress
+                if self.pos == pos_before && self.current_char().is_some() {
+                    self.repair(RepairKind::SkippedText);
+                    self.advance();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Skip a comment at the current position, unless it is a placeholder standing
+    /// alone where a member or value belongs (`in_slot`), as in `[ /* more */ ]`
+    fn skip_comments_in_slot(&mut self, in_slot: bool) {
+        if in_slot
+            && self.options.placeholders != Placeholders::Keep
+            && self.placeholder_len(self.pos).is_some()
+        {
+            return;
+        }
+        self.skip_comments();
+    }
+
+    /// Length of the comment at `pos`, without the newline ending a line comment
+    fn comment_len(&mut self, pos: usize) -> Option<usize> {
+        if self.input.char_at(pos) != Some('/') {
+            return None;
+        }
+        let mut end = pos + 2;
+        match self.input.char_at(pos + 1) {
+            Some('/') => {
+                while let Some(ch) = self.input.char_at(end).filter(|&ch| ch != '\n') {
+                    end += ch.len_utf8();
+                }
+            }
+            Some('*') => {
+                while let Some(ch) = self.input.char_at(end) {
+                    end += ch.len_utf8();
+                    if ch == '*' && self.input.char_at(end) == Some('/') {
+                        end += 1;
+                        break;
+                    }
+                }
+            }
+            _ => return None,
+        }
+        Some(end - pos)
+    }
+
+    /// Length of the placeholder for elided content at `pos`, `...`, `…` or a comment,
+    /// when only whitespace separates it from the next `,`, `}`, `]` or the end of the
+    /// input
+    fn placeholder_len(&mut self, pos: usize) -> Option<usize> {
+        let mut end = pos;
+        if self.input.char_at(pos) == Some('…') {
+            end += '…'.len_utf8();
+        } else if let Some(len) = self.comment_len(pos) {
+            end += len;
+        } else {
+            while self.input.char_at(end) == Some('.') {
+                end += 1;
+            }
+            if end - pos < 3 {
+                return None;
+            }
+        }
+        let next = self.skip_whitespace_at(end);
+        matches!(self.input.char_at(next), Some(',' | '}' | ']') | None).then_some(end - pos)
+    }
+
+    /// Whether a placeholder at `pos` stands for members of the innermost container
+    /// that are dropped
+    ///
+    /// With [`Placeholders::Null`], only members of objects are dropped, as they have
+    /// no key to give `null`.
+    fn drops_placeholder(&mut self, pos: usize) -> bool {
+        let drops = match self.options.placeholders {
+            Placeholders::Drop => true,
+            Placeholders::Null => {
+                matches!(self.state_stack.last(), Some(ParseState::Object { .. }))
+            }
+            Placeholders::Keep | Placeholders::Error => false,
+        };
+        drops && self.placeholder_len(pos).is_some()
+    }
+
+    /// Drop a placeholder for members of the innermost container at the current
+    /// position, returning whether there was one
+    fn drop_placeholder(&mut self) -> Result<bool, JsonRepairError> {
+        if self.options.placeholders == Placeholders::Error
+            && self.placeholder_len(self.pos).is_some()
+        {
+            return Err(JsonRepairError::ElidedContent { offset: self.pos });
+        }
+        if !self.drops_placeholder(self.pos) {
+            return Ok(false);
+        }
+        self.repair(RepairKind::DroppedPlaceholder);
+        self.pos += self.placeholder_len(self.pos).unwrap_or_default();
+        Ok(true)
+    }
+
+    /// Write `null` for a placeholder standing for a value at the current position,
+    /// returning whether there was one
+    fn replace_placeholder(&mut self) -> Result<bool, JsonRepairError> {
+        if self.options.placeholders == Placeholders::Keep {
+            return Ok(false);
+        }
+        let Some(len) = self.placeholder_len(self.pos) else {
+            return Ok(false);
+        };
+        if self.options.placeholders == Placeholders::Error {
+            return Err(JsonRepairError::ElidedContent { offset: self.pos });
+        }
+        self.repair(RepairKind::ReplacedPlaceholder);
+        self.pos += len;
+        self.append_str("null");
+        Ok(true)
+    }
+
+    /// Close the innermost container at the closing delimiter `ch`
+    ///
+    /// A delimiter that closes an outer container closes the inner ones first, and one
+    /// that closes nothing open is dropped.
+    fn parse_closer(&mut self, ch: char) {
+        let Some(&state) = self.state_stack.last() else {
+            return;
+        };
+        if self.closers.last() == Some(&ch) {
+            self.advance();
+        } else if self
+            .closers
+            .iter()
+            .rev()
+            .skip(1)
+            .any(|&closer| closer == ch)
+        {
+            self.repair(match state {
+                ParseState::Object { .. } => RepairKind::ClosedObject,
+                ParseState::Array { .. } => RepairKind::ClosedArray,
+            });
+        } else {
+            self.repair(RepairKind::RemovedCloser);
+            self.advance();
+            return;
+        }
+        self.append_char(state.closer());
+        self.pop_container();
+    }
+
+    /// Advance the innermost open container by one member
+    fn step(&mut self) -> Result<(), JsonRepairError> {
+        match self.state_stack.last().copied() {
+            Some(ParseState::Object {
+                expecting_key,
+                needs_comma,
+            }) => self.parse_object(expecting_key, needs_comma),
+            Some(ParseState::Array { needs_comma }) => self.parse_array(needs_comma),
+            None => Ok(()),
+        }
+    }
+
+    /// If a reasoning section opens at the current position, where it ends
+    ///
+    /// Returns `Some(None)` for a section that is never closed.
+    fn reasoning_block_at(&mut self) -> Option<Option<usize>> {
+        let tags = self.options.reasoning_tags.clone();
+        for tag in &tags {
+            let open = format!("<{}>", tag);
+            if self.input.starts_with(self.pos, &open, true) {
+                let close = format!("</{}>", tag);
+                let body = self.pos + open.len();
+                return Some(
+                    self.input
+                        .find(body, &close, true)
+                        .map(|close_at| close_at + close.len()),
+                );
+            }
+        }
+        None
+    }
+
+    /// Where the reasoning ends when its opening tag was part of the prompt
+    ///
+    /// A closing tag that appears before any opening tag means everything up to it is
+    /// reasoning.
+    fn prompt_reasoning_end(&mut self) -> Option<usize> {
+        let tags = self.options.reasoning_tags.clone();
+        tags.iter()
+            .filter_map(|tag| {
+                let close = format!("</{}>", tag);
+                let close_at = self.input.find(self.pos, &close, true)?;
+                match self.input.find(self.pos, &format!("<{}>", tag), true) {
+                    Some(open_at) if open_at < close_at => None,
+                    _ => Some(close_at + close.len()),
+                }
+            })
+            .min()
+    }
+
+    /// Move to where the JSON starts, skipping explanatory text, reasoning sections and
+    /// markdown code blocks
+    ///
+    /// Input that starts with the members of a container, as in `"name": "John"}`, is
+    /// left where it is.
+    fn skip_to_json(&mut self) -> JsonStart {
+        let skip_reasoning = self.options.skip_reasoning;
+
+        // Drop reasoning sections first, they often contain draft JSON
+        if skip_reasoning {
+            if let Some(end) = self.prompt_reasoning_end() {
+                self.repair(RepairKind::SkippedReasoning);
+                self.pos = end;
+            }
+            loop {
+                self.skip_whitespace();
+                let block = self.reasoning_block_at();
+                if block.is_some() {
+                    self.repair(RepairKind::SkippedReasoning);
+                }
+                match block {
+                    Some(Some(end)) => self.pos = end,
+                    // Unclosed at the start: the model was still reasoning
+                    Some(None) => return JsonStart::Nothing,
+                    None => break,
+                }
+            }
+        }
+
+        // Handle markdown code blocks. The block ends at the last fence of in-memory
+        // input; reader input only checks that a closing fence follows.
+        if let Some(start) = self.input.find(self.pos, "```json", false) {
+            let body = start + 7;
+            if self.input.is_streaming() {
+                if self.input.find(body, "```", false).is_some() {
+                    self.pos = body;
+                }
+            } else if let Some(end) = self.input.rfind("```")
+                && end > body
+            {
+                self.repair_at(RepairKind::StrippedCodeFence, start);
+                self.pos = body;
+                self.input.set_limit(end);
+            }
+        }
+
+        self.skip_whitespace();
+        if let Some(state) = self.headless_container() {
+            return JsonStart::Headless(state);
+        }
+
+        // A document that starts with an array is parsed as is
+        if self.current_char() == Some('[') {
+            return JsonStart::Value;
+        }
+
+        // Look for JSON start markers, skipping explanatory text. Objects are preferred;
+        // without one, fall back to the first array, then to the first thing that looks
+        // like a value.
+        let mut array_start = None;
+        let mut fallback = None;
+        let mut first_text = None;
+        while let Some(ch) = self.current_char() {
+            if ch == '{' {
+                break;
+            }
+            if skip_reasoning
+                && ch == '<'
+                && let Some(Some(end)) = self.reasoning_block_at()
+            {
+                self.repair(RepairKind::SkippedReasoning);
+                self.pos = end;
+                continue;
+            }
+            if ch == '[' {
+                // An array of objects is the JSON rather than prose in brackets
+                let next = self.skip_whitespace_at(self.pos + 1);
+                if self.input.char_at(next) == Some('{') {
+                    array_start = Some(self.pos);
+                    break;
+                }
+                array_start.get_or_insert(self.pos);
+            }
+            if fallback.is_none()
+                && (matches!(ch, '"' | '\'') || number::may_start_number(ch) || ch.is_alphabetic())
+            {
+                fallback = Some(self.pos);
+            }
+            if first_text.is_none() && !ch.is_whitespace() {
+                first_text = Some(self.pos);
+            }
+            match array_start.into_iter().chain(fallback).min() {
+                None => self.input.release(self.pos),
+                // Keep reader input bounded when no object shows up
+                Some(start) if self.input.is_streaming() && self.pos - start > LOOKAHEAD => break,
+                Some(_) => {}
+            }
+            self.advance();
+        }
+
+        let found_object = self.current_char() == Some('{');
+        if !found_object && let Some(start) = array_start.or(fallback) {
+            self.pos = start;
+        }
+        if let Some(at) = first_text
+            && at < self.pos
+        {
+            self.repair_at(RepairKind::SkippedText, at);
+        }
+        if found_object || first_text.is_some() {
+            JsonStart::Value
+        } else {
+            JsonStart::Nothing
+        }
+    }
+
+    /// The container whose members start at the current position without its opening
+    /// delimiter, as in `"name": "John"}` or `1, 2]`
+    ///
+    /// A key followed by `:` points to an object, and a closing delimiter that closes
+    /// nothing tells which container ends there. A quoted key is enough on its own,
+    /// while an unquoted key, which could start a sentence, or a value also need the
+    /// stray delimiter.
+    fn headless_container(&mut self) -> Option<ParseState> {
+        let first = self.current_char()?;
+        let limit = self.pos.saturating_add(LOOKAHEAD);
+        let quoted = matches!(first, '"' | '\'');
+        let mut pos = self.pos;
+        if quoted {
+            pos = self.string_end(pos, limit).unwrap_or(pos);
+        } else {
+            while let Some(ch) = self.input.char_at(pos)
+                && (ch.is_alphanumeric() || ch == '_')
+            {
+                pos += ch.len_utf8();
+            }
+        }
+        let key_end = pos;
+        pos = self.skip_whitespace_at(pos);
+        let keyed = key_end > self.pos && self.input.char_at(pos) == Some(':');
+
+        let valued = quoted || number::may_start_number(first);
+        if !keyed && !valued {
+            return None;
+        }
+        match self.stray_closer(limit) {
+            Some('}') if keyed => Some(ParseState::Object {
+                expecting_key: true,
+                needs_comma: false,
+            }),
+            None if keyed && quoted => Some(ParseState::Object {
+                expecting_key: true,
+                needs_comma: false,
+            }),
+            Some(']') if !keyed => Some(ParseState::Array { needs_comma: false }),
+            _ => None,
+        }
+    }
+
+    /// Open the container whose members start at the current position
+    fn open_headless(&mut self, state: ParseState) {
+        let span = self.start_span();
+        match state {
+            ParseState::Object { .. } => {
+                self.repair(RepairKind::OpenedObject);
+                self.append_char('{');
+                self.emit(Event::StartObject);
+            }
+            ParseState::Array { .. } => {
+                self.repair(RepairKind::OpenedArray);
+                self.append_char('[');
+                self.emit(Event::StartArray);
+            }
+        }
+        self.push_container(state, state.closer(), span);
+    }
+
+    /// Whether the `[` at the current position holds key/value pairs, as in
+    /// `["name": "John"]`, judging by a quoted key and `:` as its first member
+    fn holds_pairs(&mut self) -> bool {
+        let limit = self.pos.saturating_add(LOOKAHEAD);
+        let mut pos = self.skip_whitespace_at(self.pos + 1);
+        if !matches!(self.input.char_at(pos), Some('"' | '\'')) {
+            return false;
+        }
+        let Some(end) = self.string_end(pos, limit) else {
+            return false;
+        };
+        pos = self.skip_whitespace_at(end);
+        self.input.char_at(pos) == Some(':')
+    }
+
+    /// Whether the `{` at the current position holds bare values, as in
+    /// `{"red", "green"}`: strings or numbers separated by commas, with no `:` before
+    /// the matching `}`
+    fn holds_bare_values(&mut self) -> bool {
+        let limit = self.pos.saturating_add(LOOKAHEAD);
+        let mut pos = self.skip_whitespace_at(self.pos + 1);
+        match self.input.char_at(pos) {
+            Some('"' | '\'') => match self.string_end(pos, limit) {
+                Some(end) => pos = end,
+                None => return false,
+            },
+            Some(ch) if number::may_start_number(ch) => {
+                while let Some(ch) = self.input.char_at(pos)
+                    && (ch.is_alphanumeric() || matches!(ch, '.' | '-' | '+'))
+                {
+                    pos += ch.len_utf8();
+                }
+            }
+            _ => return false,
+        }
+        pos = self.skip_whitespace_at(pos);
+        if !matches!(self.input.char_at(pos), Some(',' | '}')) {
+            return false;
+        }
+
+        let mut depth = 0usize;
+        while pos < limit {
+            let Some(ch) = self.input.char_at(pos) else {
+                // Cut off: the values so far decide
+                return true;
+            };
+            match ch {
+                '"' | '\'' => {
+                    match self.string_end(pos, limit) {
+                        Some(end) => pos = end,
+                        None => return false,
+                    }
+                    continue;
+                }
+                '{' | '[' => depth += 1,
+                '}' | ']' if depth == 0 => return true,
+                '}' | ']' => depth -= 1,
+                ':' if depth == 0 => return false,
+                _ => {}
+            }
+            pos += ch.len_utf8();
+        }
+        false
+    }
+
+    /// The first position from `pos` that is not whitespace
+    fn skip_whitespace_at(&mut self, mut pos: usize) -> usize {
+        while let Some(ch) = self.input.char_at(pos)
+            && ch.is_whitespace()
+        {
+            pos += ch.len_utf8();
+        }
+        pos
+    }
+
+    /// Position after the string whose opening quote is at `start`, `None` if it does
+    /// not end before `limit`
+    fn string_end(&mut self, start: usize, limit: usize) -> Option<usize> {
+        let quote = self.input.char_at(start)?;
+        let mut pos = start + quote.len_utf8();
+        while pos < limit {
+            let ch = self.input.char_at(pos)?;
+            pos += ch.len_utf8();
+            if ch == '\\' {
+                pos += self.input.char_at(pos)?.len_utf8();
+            } else if ch == quote {
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    /// The first `}` or `]` from the current position that closes nothing, skipping
+    /// double-quoted strings and stopping at `limit`
+    fn stray_closer(&mut self, limit: usize) -> Option<char> {
+        let mut depth = 0usize;
+        let mut pos = self.pos;
+        while pos < limit {
+            let ch = self.input.char_at(pos)?;
+            match ch {
+                '"' => {
+                    pos = self.string_end(pos, limit)?;
+                    continue;
+                }
+                '{' | '[' => depth += 1,
+                '}' | ']' if depth == 0 => return Some(ch),
+                '}' | ']' => depth -= 1,
+                _ => {}
+            }
+            pos += ch.len_utf8();
+        }
+        None
+    }
+
+    /// Where the next top-level object or array starts, if only whitespace, commas and
+    /// code fences come before it
+    ///
+    /// An array counts only when its first element starts like JSON, so that prose in
+    /// brackets after the answer is not taken for another value.
+    fn next_root_value(&mut self) -> Option<usize> {
+        let mut pos = self.pos;
+        loop {
+            match self.input.char_at(pos)? {
+                '{' => return Some(pos),
+                '[' => {
+                    let first = self.skip_whitespace_at(pos + 1);
+                    let json = match self.input.char_at(first) {
+                        Some(ch) => {
+                            matches!(ch, '{' | '[' | ']' | '"' | '-') || ch.is_ascii_digit() || {
+                                ["true", "false", "null"]
+                                    .iter()
+                                    .any(|literal| self.input.starts_with(first, literal, false))
+                            }
+                        }
+                        None => true,
+                    };
+                    return json.then_some(pos);
+                }
+                ch if ch.is_whitespace() || ch == ',' => pos += ch.len_utf8(),
+                '`' if self.input.starts_with(pos, "```", false) => {
+                    pos += 3;
+                    if self.input.starts_with(pos, "json", false) {
+                        pos += 4;
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Parse again from the start, now that all top-level values are known
+    fn restart(&mut self) {
+        let root_starts = std::mem::take(&mut self.root_starts);
+        self.pos = 0;
+        self.output.clear();
+        self.state_stack.clear();
+        self.path.clear();
+        self.open_spans.clear();
+        self.closers.clear();
+        self.repairs = self.repairs.as_ref().map(|_| Vec::new());
+        self.spans = self.spans.as_ref().map(|_| Vec::new());
+        self.alignment = self.alignment.as_ref().map(|_| Vec::new());
+        self.aligned = (0, 0);
+        self.phase = Phase::Start;
+        match self.options.root_values {
+            RootValues::Last => {
+                if let Some((&last, dropped)) = root_starts.split_last() {
+                    for &start in dropped {
+                        self.repair_at(RepairKind::DroppedValue, start);
+                    }
+                    self.pos = last;
+                }
+            }
+            _ => {
+                for &start in &root_starts {
+                    self.repair_at(RepairKind::WrappedValue, start);
+                }
+                self.wrapping = true;
+            }
+        }
+    }
+
+    /// Parse the next part of the document, returning false once it is complete
+    fn parse_next(&mut self) -> Result<bool, JsonRepairError> {
+        match self.phase {
+            Phase::Start => {
+                let start = self.skip_to_json();
+                if start != JsonStart::Nothing {
+                    if self.wrapping {
+                        let span = self.start_span();
+                        self.append_char('[');
+                        self.emit(Event::StartArray);
+                        let state = ParseState::Array { needs_comma: true };
+                        self.push_container(state, state.closer(), span);
+                        self.set_segment(Segment::Index(Some(0)));
+                    } else {
+                        self.root_starts.push(self.pos);
+                    }
+                    match start {
+                        JsonStart::Headless(state) => self.open_headless(state),
+                        _ => self.parse_value()?,
+                    }
+                    self.phase = Phase::Values;
+                } else {
+                    self.repair(RepairKind::InsertedValue);
+                    self.append_str("{}");
+                    self.emit(Event::StartObject);
+                    self.emit(Event::EndObject);
+                    self.phase = Phase::Done;
+                }
+            }
+            // The array wrapping the top-level values takes one value at a time
+            Phase::Values if self.wrapping && self.state_stack.len() == 1 => {
+                if let Some(start) = self.next_root_value() {
+                    self.pos = start;
+                    self.append_char(',');
+                    if let Some(Segment::Index(index)) = self.path.last_mut() {
+                        *index = index.map(|index| index + 1);
+                    }
+                    self.parse_value()?;
+                } else {
+                    self.append_char(']');
+                    self.pop_container();
+                }
+            }
+            Phase::Values if !self.state_stack.is_empty() && self.error.is_none() => {
+                self.input.release(self.pos);
+                self.step()?;
+            }
+            Phase::Values => {
+                let mut dropped = false;
+                if !self.wrapping
+                    && self.error.is_none()
+                    && let Some(start) = self.next_root_value()
+                {
+                    let can_restart = self.writer.is_none() && self.events.is_none();
+                    match self.options.root_values {
+                        RootValues::First => {
+                            self.repair_at(RepairKind::DroppedValue, start);
+                            dropped = true;
+                        }
+                        RootValues::Last | RootValues::Wrap if can_restart => {
+                            // Parse the value only to find where the next one starts
+                            self.root_starts.push(start);
+                            self.pos = start;
+                            self.parse_value()?;
+                            return Ok(true);
+                        }
+                        _ => return Err(JsonRepairError::MultipleValues { offset: start }),
+                    }
+                }
+                if self.root_starts.len() > 1 {
+                    self.restart();
+                    return Ok(true);
+                }
+                if self.records_repairs() && !dropped {
+                    self.skip_whitespace();
+                    if self.current_char().is_some() {
+                        self.repair(RepairKind::SkippedText);
+                    }
+                }
+                self.phase = Phase::Done;
+            }
+            Phase::Done => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn parse(&mut self) -> Result<(), JsonRepairError> {
+        while self.parse_next()? {}
+        self.align();
+
+        if let Some(e) = self.input.take_error() {
+            return Err(e);
+        }
+        self.flush();
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// The next event of a parser made with [`Self::event_parser`]
+    pub(crate) fn next_event(&mut self) -> Option<Result<Event, JsonRepairError>> {
+        loop {
+            if let Some(event) = self.events.as_mut()?.pop_front() {
+                return Some(Ok(event));
+            }
+            if let Some(e) = self.input.take_error() {
+                self.phase = Phase::Done;
+                return Some(Err(e));
+            }
+            // Events stand in for the output, which only needs to last one step
+            self.output.clear();
+            match self.parse_next() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(e) => {
+                    self.phase = Phase::Done;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+
+    /// Fixes applied so far, empty for reader input
+    pub(crate) fn take_repairs(&mut self) -> Vec<Repair> {
+        self.repairs.take().unwrap_or_default()
+    }
+
+    /// Pieces of input in order, each with the range of output written for it
+    ///
+    /// The pieces cover the input up to where parsing stopped.
+    pub(crate) fn take_alignment(&mut self) -> Vec<(Range<usize>, Range<usize>)> {
+        self.alignment.take().unwrap_or_default()
+    }
+
+    /// Spans of the values parsed, `None` without the `source_map` option
+    pub(crate) fn take_spans(&mut self) -> Option<Vec<Span>> {
+        self.spans.take()
+    }
+
+    pub(crate) fn get_result(self) -> String {
+        self.output
+    }
+}
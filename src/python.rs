@@ -0,0 +1,283 @@
+//! Python bindings, enabled with the `python` feature
+//!
+//! Built with maturin (see `pyproject.toml`), the `llm_json` module is a drop-in
+//! replacement for the functions of json_repair:
+//!
+//! ```python
+//! import llm_json as json_repair
+//!
+//! json_repair.repair_json("{name: 'John'")  # '{"name": "John"}'
+//! json_repair.loads("[1, 2", logging=True)  # ([1, 2], [{'text': ..., 'context': ...}])
+//! ```
+//!
+//! Values go through Python's `json` module, so key order and the formatting of
+//! `json.dumps` are the same as with json_repair.
+
+use crate::{JsonRepairError, Repair, RepairOptions, repair_json_with_report};
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyList};
+use std::fs;
+use std::path::PathBuf;
+
+/// Characters of input shown on each side of a repair in the log
+const CONTEXT_WINDOW: usize = 10;
+
+impl From<JsonRepairError> for PyErr {
+    fn from(e: JsonRepairError) -> Self {
+        match e {
+            JsonRepairError::IoError(e) => PyOSError::new_err(e.to_string()),
+            e => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// Repair `json_str` into a Python value, along with the repairs applied
+fn repair<'py>(
+    py: Python<'py>,
+    json_str: &str,
+    skip_json_loads: bool,
+    stream_stable: bool,
+) -> PyResult<(Bound<'py, PyAny>, Vec<Repair>)> {
+    let json = py.import("json")?;
+    if !skip_json_loads && let Ok(value) = json.call_method1("loads", (json_str,)) {
+        return Ok((value, Vec::new()));
+    }
+
+    // Validation happens in Python, which keeps keys in their original order
+    let options = RepairOptions {
+        skip_json_loads: true,
+        stream_stable,
+        ..RepairOptions::default()
+    };
+    // Other Python threads run while the input is repaired
+    let report = py.detach(|| repair_json_with_report(json_str, &options))?;
+    let value = json.call_method1("loads", (report.json,))?;
+    Ok((value, report.repairs))
+}
+
+/// The repairs as json_repair logs them: a description and the surrounding input
+fn repair_log<'py>(
+    py: Python<'py>,
+    json_str: &str,
+    repairs: &[Repair],
+) -> PyResult<Bound<'py, PyList>> {
+    let entries = repairs
+        .iter()
+        .map(|repair| {
+            let before = json_str[..repair.offset]
+                .char_indices()
+                .rev()
+                .nth(CONTEXT_WINDOW - 1)
+                .map_or(0, |(index, _)| index);
+            let after = json_str[repair.offset..]
+                .char_indices()
+                .nth(CONTEXT_WINDOW)
+                .map_or(json_str.len(), |(index, _)| repair.offset + index);
+            [
+                ("text", repair.kind.to_string()),
+                ("context", json_str[before..after].to_string()),
+            ]
+            .into_py_dict(py)
+        })
+        .collect::<PyResult<Vec<Bound<'py, PyDict>>>>()?;
+    PyList::new(py, entries)
+}
+
+/// The repaired value, paired with the repair log when `logging` is set
+fn with_log<'py>(
+    py: Python<'py>,
+    json_str: &str,
+    (value, repairs): (Bound<'py, PyAny>, Vec<Repair>),
+    logging: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    if !logging {
+        return Ok(value);
+    }
+    let log = repair_log(py, json_str, &repairs)?;
+    Ok((value, log).into_pyobject(py)?.into_any())
+}
+
+/// Check the `chunk_length` of json_repair
+///
+/// It is accepted for compatibility only: the input is always read at once, since the
+/// repair needs all of it.
+fn check_chunk_length(chunk_length: isize) -> PyResult<()> {
+    if chunk_length < 0 {
+        return Err(PyValueError::new_err("chunk_length must not be negative"));
+    }
+    Ok(())
+}
+
+/// Read the whole of a file object
+fn read_fd(fd: &Bound<'_, PyAny>, chunk_length: isize) -> PyResult<String> {
+    check_chunk_length(chunk_length)?;
+    fd.call_method0("read")?.extract()
+}
+
+/// Repair a broken JSON string
+///
+/// Returns the repaired JSON as a string, or the value itself with `return_objects`.
+/// With `logging` the value is returned together with the list of repairs. When
+/// `json_fd` is given, the input is read from that file object instead of `json_str`.
+#[pyfunction]
+#[pyo3(signature = (json_str = "", return_objects = false, skip_json_loads = false, ensure_ascii = true, logging = false, json_fd = None, chunk_length = 0, *, stream_stable = false))]
+#[allow(clippy::too_many_arguments)]
+fn repair_json<'py>(
+    py: Python<'py>,
+    json_str: &str,
+    return_objects: bool,
+    skip_json_loads: bool,
+    ensure_ascii: bool,
+    logging: bool,
+    json_fd: Option<&Bound<'py, PyAny>>,
+    chunk_length: isize,
+    stream_stable: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let read;
+    let json_str = match json_fd {
+        Some(fd) => {
+            read = read_fd(fd, chunk_length)?;
+            &read
+        }
+        None => json_str,
+    };
+    let repaired = repair(py, json_str, skip_json_loads, stream_stable)?;
+    if return_objects || logging {
+        return with_log(py, json_str, repaired, logging);
+    }
+    let kwargs = [("ensure_ascii", ensure_ascii)].into_py_dict(py)?;
+    py.import("json")?
+        .call_method("dumps", (repaired.0,), Some(&kwargs))
+}
+
+/// Repair a broken JSON string into a Python value
+#[pyfunction]
+#[pyo3(signature = (json_str, skip_json_loads = false, logging = false, *, stream_stable = false))]
+fn loads<'py>(
+    py: Python<'py>,
+    json_str: &str,
+    skip_json_loads: bool,
+    logging: bool,
+    stream_stable: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let repaired = repair(py, json_str, skip_json_loads, stream_stable)?;
+    with_log(py, json_str, repaired, logging)
+}
+
+/// Repair the broken JSON read from a file object into a Python value
+#[pyfunction]
+#[pyo3(signature = (fd, skip_json_loads = false, logging = false, chunk_length = 1_024_000, *, stream_stable = false))]
+fn load<'py>(
+    py: Python<'py>,
+    fd: &Bound<'py, PyAny>,
+    skip_json_loads: bool,
+    logging: bool,
+    chunk_length: isize,
+    stream_stable: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let json_str = read_fd(fd, chunk_length)?;
+    loads(py, &json_str, skip_json_loads, logging, stream_stable)
+}
+
+/// Repair the broken JSON in the file at `filename` into a Python value
+#[pyfunction]
+#[pyo3(signature = (filename, skip_json_loads = false, logging = false, chunk_length = 1_024_000, *, stream_stable = false))]
+fn from_file<'py>(
+    py: Python<'py>,
+    filename: PathBuf,
+    skip_json_loads: bool,
+    logging: bool,
+    chunk_length: isize,
+    stream_stable: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    check_chunk_length(chunk_length)?;
+    let json_str = fs::read_to_string(filename)?;
+    loads(py, &json_str, skip_json_loads, logging, stream_stable)
+}
+
+#[pymodule]
+fn llm_json(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(repair_json, m)?)?;
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(from_file, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::io::Write;
+
+    /// Run `code` with the module imported as `json_repair`
+    fn run(code: &CStr, globals: &[(&str, &str)]) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "llm_json").unwrap();
+            llm_json(&module).unwrap();
+            let scope = PyDict::new(py);
+            scope.set_item("json_repair", module).unwrap();
+            for (name, value) in globals {
+                scope.set_item(name, value).unwrap();
+            }
+            py.run(code, Some(&scope), None)
+                .unwrap_or_else(|e| panic!("{}", e));
+        });
+    }
+
+    #[test]
+    fn test_repair_json() {
+        run(
+            cr#"
+assert json_repair.repair_json("{b: 1, a: 'é',") == '{"b": 1, "a": "\\u00e9"}'
+assert json_repair.repair_json("{b: 1, a: 'é',", ensure_ascii=False) == '{"b": 1, "a": "é"}'
+assert json_repair.repair_json('{"a": [1, 2]}', return_objects=True) == {"a": [1, 2]}
+assert json_repair.repair_json("[1, 2", True) == [1, 2]
+assert json_repair.repair_json("[1, 2", skip_json_loads=True) == "[1, 2]"
+
+value, log = json_repair.repair_json("[1, 2", logging=True)
+assert value == [1, 2]
+assert log == [{"text": "closed unterminated array", "context": "[1, 2"}]
+assert json_repair.repair_json("[1]", logging=True) == ([1], [])
+"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_loads() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"{name: 'John', tags: ['a'").unwrap();
+        let path = file.path().to_str().unwrap();
+        run(
+            cr#"
+expected = {"name": "John", "tags": ["a"]}
+assert json_repair.loads("{name: 'John', tags: ['a'") == expected
+assert json_repair.from_file(path) == expected
+with open(path) as fd:
+    assert json_repair.load(fd) == expected
+assert json_repair.from_file(path, logging=True)[0] == expected
+assert json_repair.from_file(path, chunk_length=4) == expected
+with open(path) as fd:
+    assert json_repair.load(fd, chunk_length=4) == expected
+with open(path) as fd:
+    assert json_repair.repair_json(json_fd=fd, return_objects=True) == expected
+
+try:
+    json_repair.from_file(path, chunk_length=-1)
+    assert False
+except ValueError:
+    pass
+
+try:
+    json_repair.from_file(path + ".missing")
+    assert False
+except OSError:
+    pass
+"#,
+            &[("path", path)],
+        );
+    }
+}
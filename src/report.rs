@@ -0,0 +1,385 @@
+//! What the repairer changed in the input
+
+use crate::SourceMap;
+use std::fmt;
+
+/// Kind of fix applied to the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum RepairKind {
+    /// Text before or after the JSON was skipped
+    SkippedText,
+    /// A top-level value other than the one kept was dropped, see [`RootValues`](crate::RootValues)
+    DroppedValue,
+    /// One of several top-level values was wrapped into an array
+    WrappedValue,
+    /// A reasoning section such as `<think>...</think>` was skipped
+    SkippedReasoning,
+    /// The JSON was taken out of a markdown code block
+    StrippedCodeFence,
+    /// A `//` or `/* */` comment was removed
+    RemovedComment,
+    /// A key without quotes was quoted
+    QuotedKey,
+    /// A bare word or stray character was turned into a string
+    QuotedString,
+    /// A single-quoted string was rewritten with double quotes
+    ReplacedQuotes,
+    /// A character that is not allowed raw inside a string was escaped
+    EscapedCharacter,
+    /// A string that never ends was closed
+    ClosedString,
+    /// A missing `:` after a key was inserted
+    InsertedColon,
+    /// A missing `,` between members was inserted
+    InsertedComma,
+    /// A trailing or repeated `,` was removed
+    RemovedComma,
+    /// A `}` or `]` that closes nothing open was removed
+    RemovedCloser,
+    /// An object that never ends was closed
+    ClosedObject,
+    /// An array that never ends was closed
+    ClosedArray,
+    /// An object whose `{` is missing was opened at the start of its members
+    OpenedObject,
+    /// An array whose `[` is missing was opened at the start of its elements
+    OpenedArray,
+    /// Brackets holding key/value pairs were read as an object
+    ConvertedToObject,
+    /// Braces holding bare values, or an object keyed by indices, were read as an array
+    ConvertedToArray,
+    /// A value without a key got a made-up key
+    InventedKey,
+    /// A missing value was filled in
+    InsertedValue,
+    /// A literal such as `None`, `undefined` or `True` was rewritten
+    NormalizedLiteral,
+    /// A number such as `1.`, `007`, `+5` or `1,000` was rewritten as JSON
+    NormalizedNumber,
+    /// A currency or unit written with a number was dropped
+    DroppedUnit,
+    /// A placeholder such as `...` for elided content was dropped, see
+    /// [`Placeholders`](crate::Placeholders)
+    DroppedPlaceholder,
+    /// A placeholder such as `...` for elided content was replaced with `null`
+    ReplacedPlaceholder,
+}
+
+impl fmt::Display for RepairKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RepairKind::SkippedText => "skipped text around the JSON",
+            RepairKind::DroppedValue => "dropped extra top-level value",
+            RepairKind::WrappedValue => "wrapped top-level value into an array",
+            RepairKind::SkippedReasoning => "skipped reasoning",
+            RepairKind::StrippedCodeFence => "stripped markdown code fence",
+            RepairKind::RemovedComment => "removed comment",
+            RepairKind::QuotedKey => "quoted key",
+            RepairKind::QuotedString => "quoted bare text",
+            RepairKind::ReplacedQuotes => "replaced single quotes",
+            RepairKind::EscapedCharacter => "escaped character",
+            RepairKind::ClosedString => "closed unterminated string",
+            RepairKind::InsertedColon => "inserted missing colon",
+            RepairKind::InsertedComma => "inserted missing comma",
+            RepairKind::RemovedComma => "removed extra comma",
+            RepairKind::RemovedCloser => "removed stray closing delimiter",
+            RepairKind::ClosedObject => "closed unterminated object",
+            RepairKind::ClosedArray => "closed unterminated array",
+            RepairKind::OpenedObject => "inserted missing opening brace",
+            RepairKind::OpenedArray => "inserted missing opening bracket",
+            RepairKind::ConvertedToObject => "converted array to object",
+            RepairKind::ConvertedToArray => "converted object to array",
+            RepairKind::InventedKey => "invented missing key",
+            RepairKind::InsertedValue => "inserted missing value",
+            RepairKind::NormalizedLiteral => "normalized literal",
+            RepairKind::NormalizedNumber => "normalized number",
+            RepairKind::DroppedUnit => "dropped unit of number",
+            RepairKind::DroppedPlaceholder => "dropped placeholder",
+            RepairKind::ReplacedPlaceholder => "replaced placeholder with null",
+        })
+    }
+}
+
+impl RepairKind {
+    /// The mistake behind the fix, as told to the model, `None` when it is not one
+    fn problem(self) -> Option<&'static str> {
+        Some(match self {
+            RepairKind::SkippedReasoning => return None,
+            RepairKind::SkippedText => "text outside the JSON",
+            RepairKind::DroppedValue | RepairKind::WrappedValue => "more than one top-level value",
+            RepairKind::StrippedCodeFence => "a markdown code fence",
+            RepairKind::RemovedComment => "a comment",
+            RepairKind::QuotedKey => "an unquoted key",
+            RepairKind::QuotedString => "unquoted text",
+            RepairKind::ReplacedQuotes => "single quotes",
+            RepairKind::EscapedCharacter => "an unescaped character",
+            RepairKind::ClosedString => "an unterminated string",
+            RepairKind::InsertedColon => "a missing `:`",
+            RepairKind::InsertedComma => "a missing `,`",
+            RepairKind::RemovedComma => "an extra `,`",
+            RepairKind::RemovedCloser => "a `}` or `]` that closes nothing",
+            RepairKind::ClosedObject => "a missing closing `}`",
+            RepairKind::ClosedArray => "a missing closing `]`",
+            RepairKind::OpenedObject => "a missing opening `{`",
+            RepairKind::OpenedArray => "a missing opening `[`",
+            RepairKind::ConvertedToObject => "an object written in `[]`",
+            RepairKind::ConvertedToArray => "an array written as an object",
+            RepairKind::InventedKey => "a value without a key",
+            RepairKind::InsertedValue => "a missing value",
+            RepairKind::NormalizedLiteral => "a literal that is not JSON",
+            RepairKind::NormalizedNumber => "a number that is not JSON",
+            RepairKind::DroppedUnit => "a number with a unit",
+            RepairKind::DroppedPlaceholder | RepairKind::ReplacedPlaceholder => {
+                "a placeholder for elided content"
+            }
+        })
+    }
+
+    /// How much the fix guesses at what was meant, from 0 (certain) to 1
+    ///
+    /// Dropping wrappers or closing a container at the end of the input is cheap,
+    /// while making up a key or a value, turning prose into a string or deciding where
+    /// a string ends is expensive.
+    pub fn cost(self) -> f64 {
+        match self {
+            RepairKind::SkippedReasoning | RepairKind::StrippedCodeFence => 0.0,
+            RepairKind::RemovedComment | RepairKind::RemovedComma => 0.01,
+            RepairKind::QuotedKey
+            | RepairKind::ReplacedQuotes
+            | RepairKind::EscapedCharacter
+            | RepairKind::NormalizedLiteral
+            | RepairKind::ClosedObject
+            | RepairKind::ClosedArray
+            | RepairKind::RemovedCloser => 0.02,
+            RepairKind::SkippedText
+            | RepairKind::InsertedComma
+            | RepairKind::NormalizedNumber
+            | RepairKind::WrappedValue => 0.05,
+            RepairKind::InsertedColon
+            | RepairKind::DroppedUnit
+            | RepairKind::OpenedObject
+            | RepairKind::OpenedArray
+            | RepairKind::ConvertedToObject
+            | RepairKind::ConvertedToArray => 0.1,
+            RepairKind::ClosedString => 0.2,
+            RepairKind::InsertedValue
+            | RepairKind::DroppedValue
+            | RepairKind::DroppedPlaceholder
+            | RepairKind::ReplacedPlaceholder => 0.25,
+            RepairKind::QuotedString => 0.35,
+            RepairKind::InventedKey => 0.5,
+        }
+    }
+}
+
+/// Confidence that `repairs` recovered what was meant, from 0 to 1
+///
+/// Each repair keeps `1 - cost` of the confidence, so valid input scores 1 and every
+/// guess lowers the score.
+pub fn confidence(repairs: &[Repair]) -> f64 {
+    repairs
+        .iter()
+        .map(|repair| 1.0 - repair.kind.cost())
+        .product()
+}
+
+/// A fix applied to the input
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Repair {
+    pub kind: RepairKind,
+    /// Byte offset in the input where the fix applies
+    pub offset: usize,
+    /// JSON Pointer to the value the fix belongs to, empty for the whole document
+    pub path: String,
+}
+
+impl Repair {
+    /// Line and column of the fix in `input`, both starting at 1
+    ///
+    /// The column counts characters, not bytes.
+    pub fn line_col(&self, input: &str) -> (usize, usize) {
+        let before = input.get(..self.offset).unwrap_or(input);
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+/// Repaired JSON together with every fix that was needed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The repaired JSON string
+    pub json: String,
+    /// Fixes in the order they were applied, empty when the input was valid
+    pub repairs: Vec<Repair>,
+    /// Where each value came from in the input, with the `source_map` option
+    pub source_map: Option<SourceMap>,
+}
+
+impl RepairReport {
+    /// Whether the input was valid JSON as it was
+    pub fn is_valid(&self) -> bool {
+        self.repairs.is_empty()
+    }
+
+    /// Confidence in the repair, from 0 to 1, see [`confidence`]
+    pub fn confidence(&self) -> f64 {
+        confidence(&self.repairs)
+    }
+
+    /// A short message telling the model what was wrong with `input`, to send back when
+    /// asking it again; `None` when there was nothing to tell
+    ///
+    /// ```rust
+    /// use llm_json::{repair_json_with_report, RepairOptions};
+    ///
+    /// let input = "{\"items\": [{\"name\": 'a'}";
+    /// let report = repair_json_with_report(input, &RepairOptions::default()).unwrap();
+    /// assert_eq!(
+    ///     report.feedback(input).unwrap(),
+    ///     "Your JSON had single quotes in `items[0].name` at line 1, a missing closing `]` \
+    ///      for `items` and a missing closing `}`; respond with valid JSON only."
+    /// );
+    /// ```
+    pub fn feedback(&self, input: &str) -> Option<String> {
+        let mut seen = Vec::new();
+        let mut problems = Vec::new();
+        for repair in &self.repairs {
+            let Some(problem) = repair.kind.problem() else {
+                continue;
+            };
+            if seen.contains(&(repair.kind, &repair.path)) {
+                continue;
+            }
+            seen.push((repair.kind, &repair.path));
+
+            // Closing is at the end of the input, where the line tells nothing
+            let closes = matches!(
+                repair.kind,
+                RepairKind::ClosedObject | RepairKind::ClosedArray
+            );
+            let mut problem = problem.to_string();
+            if let Some(path) = display_path(&repair.path) {
+                let preposition = if closes { "for" } else { "in" };
+                problem.push_str(&format!(" {} `{}`", preposition, path));
+            }
+            if !closes {
+                problem.push_str(&format!(" at line {}", repair.line_col(input).0));
+            }
+            problems.push(problem);
+        }
+
+        if problems.len() > MAX_PROBLEMS {
+            let more = problems.len() - MAX_PROBLEMS + 1;
+            problems.truncate(MAX_PROBLEMS - 1);
+            problems.push(format!("{} more problems", more));
+        }
+        let last = problems.pop()?;
+        let problems = if problems.is_empty() {
+            last
+        } else {
+            format!("{} and {}", problems.join(", "), last)
+        };
+        Some(format!(
+            "Your JSON had {}; respond with valid JSON only.",
+            problems
+        ))
+    }
+}
+
+/// Problems listed in feedback before the rest are only counted
+const MAX_PROBLEMS: usize = 4;
+
+/// A JSON Pointer as it reads in a message, like `items[0].name`; `None` for the root
+fn display_path(pointer: &str) -> Option<String> {
+    let mut path = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+            path.push_str(&format!("[{}]", segment));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&segment);
+        }
+    }
+    (!path.is_empty()).then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confidence() {
+        let report = |kinds: &[RepairKind]| RepairReport {
+            repairs: kinds
+                .iter()
+                .map(|&kind| Repair {
+                    kind,
+                    offset: 0,
+                    path: String::new(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(report(&[]).confidence(), 1.0);
+        assert_eq!(report(&[RepairKind::StrippedCodeFence]).confidence(), 1.0);
+
+        let closed = report(&[RepairKind::ClosedArray, RepairKind::ClosedObject]).confidence();
+        let invented = report(&[RepairKind::InventedKey]).confidence();
+        assert!((closed - 0.98 * 0.98).abs() < 1e-9);
+        assert!(invented < closed);
+
+        let guesses = report(&[RepairKind::QuotedString; 20]).confidence();
+        assert!(guesses > 0.0 && guesses < 0.01);
+    }
+
+    #[test]
+    fn test_feedback() {
+        let repair = |kind, offset, path: &str| Repair {
+            kind,
+            offset,
+            path: path.to_string(),
+        };
+        let input = "{\"a\": {\"b\": [1\n  2";
+        let report = RepairReport {
+            repairs: vec![
+                repair(RepairKind::SkippedReasoning, 0, ""),
+                repair(RepairKind::InsertedComma, 17, "/a/b/0"),
+                repair(RepairKind::ClosedArray, 18, "/a/b"),
+                repair(RepairKind::ClosedObject, 18, "/a"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            report.feedback(input).unwrap(),
+            "Your JSON had a missing `,` in `a.b[0]` at line 2, a missing closing `]` for \
+             `a.b` and a missing closing `}` for `a`; respond with valid JSON only."
+        );
+
+        let many = RepairReport {
+            repairs: (0..6)
+                .map(|i| repair(RepairKind::QuotedKey, 0, &format!("/k{}", i)))
+                .collect(),
+            ..Default::default()
+        };
+        assert!(
+            many.feedback("")
+                .unwrap()
+                .ends_with("`k2` at line 1 and 3 more problems; respond with valid JSON only.")
+        );
+
+        let skipped = RepairReport {
+            repairs: vec![repair(RepairKind::SkippedReasoning, 0, "")],
+            ..Default::default()
+        };
+        assert_eq!(skipped.feedback(""), None);
+        assert_eq!(RepairReport::default().feedback(""), None);
+        assert_eq!(display_path("/a~1b/0/c").as_deref(), Some("a/b[0].c"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Serde helpers for fields that hold broken JSON
+//!
+//! Raw LLM outputs are often stored as strings inside otherwise well-formed documents
+//! (database rows, config files, API payloads). These helpers repair such a field and
+//! deserialize it into a concrete type while the outer document is deserialized normally.
+//!
+//! ```rust
+//! use llm_json::serde::Repaired;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct Answer {
+//!     name: String,
+//!     age: u32,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Row {
+//!     id: u64,
+//!     #[serde(deserialize_with = "llm_json::serde::repaired")]
+//!     answer: Answer,
+//!     raw: Repaired<Vec<u32>>,
+//! }
+//!
+//! let row: Row = serde_json::from_str(
+//!     r#"{"id": 1, "answer": "{name: 'John', age: 30,", "raw": "[1, 2, 3"}"#,
+//! )
+//! .unwrap();
+//! assert_eq!(row.answer.name, "John");
+//! assert_eq!(row.answer.age, 30);
+//! assert_eq!(*row.raw, vec![1, 2, 3]);
+//! ```
+
+use crate::{RepairOptions, loads};
+use ::serde::de::{self, DeserializeOwned, Deserializer};
+use ::serde::ser::{self, Serializer};
+use ::serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::ops::{Deref, DerefMut};
+
+/// Deserialize a field holding broken JSON text into `T` using the default options
+///
+/// Meant to be used as `#[serde(deserialize_with = "llm_json::serde::repaired")]`.
+/// Fields that already hold structured data (e.g. a nested object in a config file)
+/// are passed through untouched.
+pub fn repaired<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    repaired_with(deserializer, &RepairOptions::default())
+}
+
+/// Deserialize a field holding broken JSON text into `T` using custom options
+///
+/// `deserialize_with` cannot take arguments, so wrap this in a function of your own:
+///
+/// ```rust
+/// use llm_json::RepairOptions;
+/// use serde::Deserializer;
+/// use serde_json::Value;
+///
+/// fn lenient<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+///     let options = RepairOptions {
+///         skip_reasoning: true,
+///         ..Default::default()
+///     };
+///     llm_json::serde::repaired_with(deserializer, &options)
+/// }
+/// ```
+pub fn repaired_with<'de, D, T>(deserializer: D, options: &RepairOptions) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = match Value::deserialize(deserializer)? {
+        Value::String(text) => loads(&text, options).map_err(de::Error::custom)?,
+        value => value,
+    };
+    T::deserialize(value).map_err(de::Error::custom)
+}
+
+/// A value deserialized from a field holding broken JSON text
+///
+/// Deserializes like [`repaired`] and serializes back to a JSON string, so it
+/// round-trips through the same string column it was read from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Repaired<T>(pub T);
+
+impl<T> Repaired<T> {
+    /// Unwrap the repaired value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Repaired<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Repaired<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Repaired<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        repaired(deserializer).map(Repaired)
+    }
+}
+
+impl<T: Serialize> Serialize for Repaired<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(&self.0).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Row {
+        #[serde(deserialize_with = "repaired")]
+        person: Person,
+        #[serde(default, deserialize_with = "repaired")]
+        tags: Option<Vec<String>>,
+    }
+
+    #[test]
+    fn test_repaired_field() {
+        let row: Row =
+            serde_json::from_value(json!({"person": "{name: 'John', age: 30", "tags": "['a',"}))
+                .unwrap();
+        assert_eq!(
+            row.person,
+            Person {
+                name: "John".to_string(),
+                age: 30
+            }
+        );
+        assert_eq!(row.tags, Some(vec!["a".to_string()]));
+
+        // Structured values and missing optional fields pass through
+        let row: Row =
+            serde_json::from_value(json!({"person": {"name": "Jane", "age": 25}})).unwrap();
+        assert_eq!(row.person.name, "Jane");
+        assert_eq!(row.tags, None);
+    }
+
+    #[test]
+    fn test_repaired_type_mismatch() {
+        let result = serde_json::from_value::<Row>(json!({"person": "{name: 'John'}"}));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_repaired_wrapper_round_trip() {
+        let value: Repaired<Vec<i64>> = serde_json::from_value(json!("[1, 2, 3,")).unwrap();
+        assert_eq!(*value, vec![1, 2, 3]);
+
+        let serialized = serde_json::to_value(&value).unwrap();
+        assert_eq!(serialized, json!("[1,2,3]"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Where each value of the repaired JSON came from in the input
+
+use std::ops::Range;
+
+/// A value of the repaired JSON and the text behind it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Span {
+    /// JSON Pointer to the value
+    pub(crate) pointer: String,
+    /// Byte range of the value in the input
+    pub(crate) input: Range<usize>,
+    /// Byte range of the value in the parser output, before validation reformats it
+    pub(crate) output: Range<usize>,
+}
+
+/// Byte spans in the input of every value of the repaired JSON, by JSON Pointer
+///
+/// Values the repairer filled in have an empty span where they were inserted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    spans: Vec<Span>,
+}
+
+impl SourceMap {
+    pub(crate) fn new(spans: Vec<Span>) -> Self {
+        Self { spans }
+    }
+
+    /// Byte range in the input of the value at `pointer`
+    ///
+    /// When a key is repeated, the last value wins, as it does in the repaired JSON.
+    pub fn get(&self, pointer: &str) -> Option<Range<usize>> {
+        self.spans
+            .iter()
+            .rev()
+            .find(|span| span.pointer == pointer)
+            .map(|span| span.input.clone())
+    }
+
+    /// JSON Pointers and input byte ranges of all values, in input order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Range<usize>)> {
+        self.spans
+            .iter()
+            .map(|span| (span.pointer.as_str(), span.input.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Input byte range of the innermost value holding the position of `error` in
+    /// `output`, the parser output it was raised on
+    pub(crate) fn locate(&self, output: &str, error: &serde_json::Error) -> Option<Range<usize>> {
+        let line_start = if error.line() <= 1 {
+            0
+        } else {
+            output
+                .match_indices('\n')
+                .nth(error.line() - 2)
+                .map(|(index, _)| index + 1)?
+        };
+        let offset = line_start + error.column().saturating_sub(1);
+        self.spans
+            .iter()
+            .filter(|span| {
+                span.output.start <= offset && offset < span.output.end.max(span.output.start + 1)
+            })
+            .min_by_key(|span| span.output.len())
+            .map(|span| span.input.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let span = |pointer: &str, input: Range<usize>, output: Range<usize>| Span {
+            pointer: pointer.to_string(),
+            input,
+            output,
+        };
+        let output = "{\"a\":[1,\"x\" 2]}";
+        let map = SourceMap::new(vec![
+            span("", 0..20, 0..15),
+            span("/a", 4..19, 5..14),
+            span("/a/0", 5..6, 6..7),
+            span("/a/1", 8..12, 8..11),
+        ]);
+        assert_eq!(map.get("/a/1"), Some(8..12));
+        assert_eq!(map.get("/b"), None);
+        assert_eq!(map.iter().count(), 4);
+
+        let error = serde_json::from_str::<serde_json::Value>(output).unwrap_err();
+        assert_eq!(error.column(), 13);
+        assert_eq!(map.locate(output, &error), Some(4..19));
+    }
+}
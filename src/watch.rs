@@ -0,0 +1,330 @@
+//! Callbacks on JSON paths while the input streams in
+
+use crate::{JsonRepairError, RepairKind, RepairOptions, repair_json_with_report};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+type CompleteFn<'a> = Box<dyn FnMut(&str, &Value) + 'a>;
+type PartialStringFn<'a> = Box<dyn FnMut(&str, &str) + 'a>;
+
+/// What a subscription is called with
+enum Callback<'a> {
+    Complete(CompleteFn<'a>),
+    PartialString(PartialStringFn<'a>),
+}
+
+struct Subscription<'a> {
+    /// Segments of the JSON Pointer pattern, `*` matching any one segment
+    pattern: Vec<String>,
+    callback: Callback<'a>,
+    /// Last string passed to the callback by pointer; for `Complete`, the values done
+    seen: HashMap<String, String>,
+}
+
+impl Subscription<'_> {
+    fn matches(&self, pointer: &str) -> bool {
+        let mut segments = pointer.split('/').skip(1);
+        self.pattern.iter().all(|pattern| {
+            segments
+                .next()
+                .is_some_and(|s| pattern == "*" || pattern == s)
+        }) && segments.next().is_none()
+    }
+
+    fn notify(&mut self, pointer: &str, value: &Value, complete: bool) {
+        match &mut self.callback {
+            Callback::Complete(callback) => {
+                if complete && !self.seen.contains_key(pointer) {
+                    self.seen.insert(pointer.to_string(), String::new());
+                    callback(pointer, value);
+                }
+            }
+            Callback::PartialString(callback) => {
+                if let Value::String(text) = value
+                    && self.seen.get(pointer) != Some(text)
+                {
+                    self.seen.insert(pointer.to_string(), text.clone());
+                    callback(pointer, text);
+                }
+            }
+        }
+    }
+}
+
+/// Calls back when values at chosen JSON paths complete while the input streams in
+///
+/// Paths are JSON Pointers in which a `*` segment matches any key or index, such as
+/// `/steps/*`. A value is complete once the input holds all of it: a container when its
+/// closing bracket arrives, a string when its closing quote arrives and other values
+/// when something follows them. Values that are still open when the input ends are
+/// completed by [`PathWatcher::finish`], as the repair closes them.
+///
+/// Each chunk repairs everything received so far again, as `repair_stream` does, so the
+/// total work grows with the square of the input: fine for a model's answer, slow for
+/// megabytes pushed in small chunks. Empty chunks are free.
+///
+/// Pointers are those of the repaired value so far. With
+/// [`RootValues::Last`](crate::RootValues::Last) they refer to the latest top-level
+/// value, whose values are reported even where the previous one had the same pointers.
+/// With [`Wrap`](crate::RootValues::Wrap) a second top-level value moves the first
+/// under `/0`, so a value already reported may be reported again under its new pointer.
+///
+/// # Examples
+///
+/// ```rust
+/// use llm_json::{PathWatcher, RepairOptions};
+///
+/// let mut started = Vec::new();
+/// let mut watcher = PathWatcher::new(&RepairOptions::default());
+/// watcher.on_complete("/steps/*", |pointer, step| started.push((pointer.to_string(), step.clone())));
+/// for chunk in [r#"{"steps": [{"tool": "se"#, r#"arch"}, {"tool": "#, r#""read"}]}"#] {
+///     watcher.push(chunk).unwrap();
+/// }
+/// watcher.finish().unwrap();
+/// assert_eq!(started[0].0, "/steps/0");
+/// assert_eq!(started[1].1["tool"], "read");
+/// ```
+pub struct PathWatcher<'a> {
+    options: RepairOptions,
+    buffer: String,
+    subscriptions: Vec<Subscription<'a>>,
+    /// Where the top-level value reported on starts in the input
+    root_start: Option<usize>,
+}
+
+impl<'a> PathWatcher<'a> {
+    /// Watcher repairing the input with `options`, of which `skip_json_loads` and
+    /// `source_map` are always set
+    pub fn new(options: &RepairOptions) -> Self {
+        Self {
+            options: RepairOptions {
+                skip_json_loads: true,
+                source_map: true,
+                ..options.clone()
+            },
+            buffer: String::new(),
+            subscriptions: Vec::new(),
+            root_start: None,
+        }
+    }
+
+    /// Call `callback` with the pointer and value of each value matching `pattern`, once,
+    /// when it completes
+    pub fn on_complete(
+        &mut self,
+        pattern: &str,
+        callback: impl FnMut(&str, &Value) + 'a,
+    ) -> &mut Self {
+        self.subscribe(pattern, Callback::Complete(Box::new(callback)))
+    }
+
+    /// Call `callback` with the pointer and text of each string matching `pattern`,
+    /// every time more of it arrives
+    pub fn on_partial_string(
+        &mut self,
+        pattern: &str,
+        callback: impl FnMut(&str, &str) + 'a,
+    ) -> &mut Self {
+        self.subscribe(pattern, Callback::PartialString(Box::new(callback)))
+    }
+
+    fn subscribe(&mut self, pattern: &str, callback: Callback<'a>) -> &mut Self {
+        self.subscriptions.push(Subscription {
+            pattern: pattern.split('/').skip(1).map(str::to_string).collect(),
+            callback,
+            seen: HashMap::new(),
+        });
+        self
+    }
+
+    /// Append a chunk of input and call back for what it completed
+    pub fn push(&mut self, chunk: &str) -> Result<(), JsonRepairError> {
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        self.buffer.push_str(chunk);
+        self.notify(false).map(drop)
+    }
+
+    /// End the input, completing every value still open, and return the repaired value
+    pub fn finish(mut self) -> Result<Value, JsonRepairError> {
+        self.notify(true)
+    }
+
+    fn notify(&mut self, complete_all: bool) -> Result<Value, JsonRepairError> {
+        let report = repair_json_with_report(&self.buffer, &self.options)?;
+        let value: Value = serde_json::from_str(&report.json)?;
+
+        // A new top-level value, kept instead of the previous one, is reported afresh
+        let source_map = report.source_map.as_ref();
+        let root_start = source_map
+            .and_then(|map| map.get(""))
+            .map(|span| span.start);
+        if root_start != self.root_start {
+            for subscription in &mut self.subscriptions {
+                subscription.seen.clear();
+            }
+            self.root_start = root_start;
+        }
+
+        // Values the repair had to close or fill in are still arriving
+        let open: HashSet<&str> = report
+            .repairs
+            .iter()
+            .filter(|repair| {
+                matches!(
+                    repair.kind,
+                    RepairKind::ClosedString
+                        | RepairKind::ClosedObject
+                        | RepairKind::ClosedArray
+                        | RepairKind::InsertedValue
+                )
+            })
+            .map(|repair| repair.path.as_str())
+            .collect();
+
+        for (pointer, span) in source_map.iter().flat_map(|map| map.iter()) {
+            if !self.subscriptions.iter().any(|s| s.matches(pointer)) {
+                continue;
+            }
+            let Some(node) = value.pointer(pointer) else {
+                continue;
+            };
+            let complete = complete_all
+                || (!open.contains(pointer)
+                    && (span.end < self.buffer.len()
+                        || self.buffer[..span.end].ends_with(['"', '\'', '}', ']'])));
+            for subscription in &mut self.subscriptions {
+                if subscription.matches(pointer) {
+                    subscription.notify(pointer, node, complete);
+                }
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RootValues;
+    use serde_json::json;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_watch() {
+        let calls = RefCell::new(Vec::new());
+        let mut watcher = PathWatcher::new(&RepairOptions::default());
+        watcher
+            .on_complete("/steps/*", |pointer, value| {
+                calls
+                    .borrow_mut()
+                    .push(format!("{} done: {}", pointer, value))
+            })
+            .on_complete("/count", |pointer, value| {
+                calls
+                    .borrow_mut()
+                    .push(format!("{} done: {}", pointer, value))
+            })
+            .on_partial_string("/answer", |pointer, text| {
+                calls
+                    .borrow_mut()
+                    .push(format!("{} so far: {}", pointer, text))
+            });
+
+        let chunks = [
+            "<think>plan</think>{\"steps\": [{\"n\": 1}",
+            ", {\"n\": 2",
+            "}], \"count\": 1",
+            "2, \"answer\": \"Hel",
+            "lo",
+            "\", \"extra\": [",
+        ];
+        let mut after_chunk = Vec::new();
+        for chunk in chunks {
+            watcher.push(chunk).unwrap();
+            after_chunk.push(calls.borrow().len());
+        }
+        let value = watcher.finish().unwrap();
+
+        assert_eq!(
+            calls.into_inner(),
+            vec![
+                r#"/steps/0 done: {"n":1}"#,
+                r#"/steps/1 done: {"n":2}"#,
+                "/count done: 12",
+                "/answer so far: Hel",
+                "/answer so far: Hello",
+            ]
+        );
+        // Each value is reported by the chunk that completes it
+        assert_eq!(after_chunk, vec![1, 1, 2, 4, 5, 5]);
+        assert_eq!(value["extra"], json!([]));
+    }
+
+    #[test]
+    fn test_finish() {
+        let calls = RefCell::new(Vec::new());
+        let mut watcher = PathWatcher::new(&RepairOptions::default());
+        watcher.on_complete("/*", |pointer, value| {
+            calls
+                .borrow_mut()
+                .push((pointer.to_string(), value.clone()))
+        });
+        watcher.push("{\"a\": [1, 2], \"b\": \"cut").unwrap();
+        assert_eq!(calls.borrow().len(), 1);
+        watcher.finish().unwrap();
+        assert_eq!(
+            calls.into_inner(),
+            vec![
+                ("/a".to_string(), json!([1, 2])),
+                ("/b".to_string(), json!("cut")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let calls = RefCell::new(Vec::new());
+        let mut watcher = PathWatcher::new(&RepairOptions {
+            root_values: RootValues::Wrap,
+            ..RepairOptions::default()
+        });
+        for pattern in ["/a", "/*/a"] {
+            watcher.on_complete(pattern, |pointer, value| {
+                calls
+                    .borrow_mut()
+                    .push(format!("{} done: {}", pointer, value))
+            });
+        }
+        for chunk in ["{\"a\": 1}", "", "{\"a\": 2}"] {
+            watcher.push(chunk).unwrap();
+        }
+        watcher.finish().unwrap();
+        // The first value moves under `/0` once the second one arrives
+        assert_eq!(
+            calls.into_inner(),
+            vec!["/a done: 1", "/0/a done: 1", "/1/a done: 2"]
+        );
+    }
+
+    #[test]
+    fn test_last() {
+        let calls = RefCell::new(Vec::new());
+        let mut watcher = PathWatcher::new(&RepairOptions {
+            root_values: RootValues::Last,
+            ..RepairOptions::default()
+        });
+        watcher.on_complete("/a", |pointer, value| {
+            calls
+                .borrow_mut()
+                .push(format!("{} done: {}", pointer, value))
+        });
+        for chunk in ["{\"a\": 1}", "{\"a\": 2}"] {
+            watcher.push(chunk).unwrap();
+        }
+        watcher.finish().unwrap();
+        // The pointer is reported again for the value that replaced the first
+        assert_eq!(calls.into_inner(), vec!["/a done: 1", "/a done: 2"]);
+    }
+}